@@ -0,0 +1,198 @@
+//! Derive macros for rs_gl.
+
+use proc_macro::TokenStream;
+use proc_macro2::TokenStream as TokenStream2;
+use quote::quote;
+use syn::{parse_macro_input, spanned::Spanned, Data, DeriveInput, Fields, Ident, LitInt, LitStr};
+
+/// Implements `rs_gl::vertex::VertexLayout` for a `#[repr(C)]` struct. Every field must be
+/// marked with `#[vertex(usage = "...")]`, other keys are optional:
+///
+/// - `usage = "Normal"` - a `VertexAttributeUsage` variant, such as `"Color"` or `"Custom(0)"`.
+/// - `location = 2` - shader location, index of the field by default.
+/// - `divisor = 1` - fetch rate of the attribute, 0 by default.
+/// - `normalized` - marks integer attribute as normalized.
+/// - `integer` - marks integer attribute as read as integer in shader.
+///
+/// Data type and size of each attribute are taken from the field type (see
+/// `VertexAttributeType`), offsets of the fields are checked at compile time to match the
+/// tightly packed layout expected by vertex buffers.
+#[proc_macro_derive(VertexLayout, attributes(vertex))]
+pub fn vertex_layout(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    match impl_vertex_layout(&input) {
+        Ok(tokens) => tokens.into(),
+        Err(error) => error.to_compile_error().into(),
+    }
+}
+
+struct VertexField {
+    ident: Ident,
+    ty: syn::Type,
+    usage: TokenStream2,
+    location: u8,
+    divisor: u8,
+    normalized: bool,
+    integer: bool,
+}
+
+fn impl_vertex_layout(input: &DeriveInput) -> syn::Result<TokenStream2> {
+    if !input.generics.params.is_empty() {
+        return Err(syn::Error::new(
+            input.generics.span(),
+            "VertexLayout cannot be derived for generic types",
+        ));
+    }
+
+    if !is_repr_c(input)? {
+        return Err(syn::Error::new(
+            input.ident.span(),
+            "VertexLayout requires #[repr(C)]",
+        ));
+    }
+
+    let fields = match &input.data {
+        Data::Struct(data) => match &data.fields {
+            Fields::Named(fields) => &fields.named,
+            _ => {
+                return Err(syn::Error::new(
+                    input.ident.span(),
+                    "VertexLayout requires a struct with named fields",
+                ))
+            }
+        },
+        _ => {
+            return Err(syn::Error::new(
+                input.ident.span(),
+                "VertexLayout can be derived for structs only",
+            ))
+        }
+    };
+
+    let fields = fields
+        .iter()
+        .enumerate()
+        .map(|(index, field)| parse_field(index, field))
+        .collect::<syn::Result<Vec<_>>>()?;
+
+    let name = &input.ident;
+    let count = fields.len();
+
+    let descriptors = fields.iter().map(|field| {
+        let VertexField {
+            ty,
+            usage,
+            location,
+            divisor,
+            normalized,
+            integer,
+            ..
+        } = field;
+        quote! {
+            ::rs_gl::vertex_buffer::VertexAttributeDescriptor {
+                usage: ::rs_gl::vertex_buffer::VertexAttributeUsage::#usage,
+                data_type: <#ty as ::rs_gl::vertex::VertexAttributeType>::DATA_TYPE,
+                size: <#ty as ::rs_gl::vertex::VertexAttributeType>::SIZE,
+                divisor: #divisor,
+                shader_location: #location,
+                normalized: #normalized,
+                integer: #integer,
+            }
+        }
+    });
+
+    let mut offset = quote! { 0usize };
+    let mut offset_checks = Vec::new();
+    for field in fields.iter() {
+        let ident = &field.ident;
+        let ty = &field.ty;
+        let message = format!(
+            "field `{}` of `{}` is not tightly packed, vertex layout would not match its memory",
+            ident, name
+        );
+        offset_checks.push(quote! {
+            assert!(::core::mem::offset_of!(#name, #ident) == #offset, #message);
+        });
+        offset = quote! { #offset + ::core::mem::size_of::<#ty>() };
+    }
+    let size_message = format!("`{}` has trailing padding", name);
+
+    Ok(quote! {
+        impl ::rs_gl::vertex::VertexLayout for #name {
+            fn layout() -> &'static [::rs_gl::vertex_buffer::VertexAttributeDescriptor] {
+                static LAYOUT: [::rs_gl::vertex_buffer::VertexAttributeDescriptor; #count] = [
+                    #(#descriptors),*
+                ];
+                &LAYOUT
+            }
+        }
+
+        const _: () = {
+            #(#offset_checks)*
+            assert!(::core::mem::size_of::<#name>() == #offset, #size_message);
+        };
+    })
+}
+
+fn is_repr_c(input: &DeriveInput) -> syn::Result<bool> {
+    let mut repr_c = false;
+    for attr in input.attrs.iter().filter(|a| a.path().is_ident("repr")) {
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("C") {
+                repr_c = true;
+            }
+            Ok(())
+        })?;
+    }
+    Ok(repr_c)
+}
+
+fn parse_field(index: usize, field: &syn::Field) -> syn::Result<VertexField> {
+    let mut usage = None;
+    let mut location = None;
+    let mut divisor = 0;
+    let mut normalized = false;
+    let mut integer = false;
+
+    for attr in field.attrs.iter().filter(|a| a.path().is_ident("vertex")) {
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("usage") {
+                let value: LitStr = meta.value()?.parse()?;
+                usage = Some(value.parse::<syn::Expr>()?);
+            } else if meta.path.is_ident("location") {
+                let value: LitInt = meta.value()?.parse()?;
+                location = Some(value.base10_parse::<u8>()?);
+            } else if meta.path.is_ident("divisor") {
+                let value: LitInt = meta.value()?.parse()?;
+                divisor = value.base10_parse::<u8>()?;
+            } else if meta.path.is_ident("normalized") {
+                normalized = true;
+            } else if meta.path.is_ident("integer") {
+                integer = true;
+            } else {
+                return Err(meta.error("unknown vertex attribute key"));
+            }
+            Ok(())
+        })?;
+    }
+
+    let usage = usage.ok_or_else(|| {
+        syn::Error::new(field.span(), "missing #[vertex(usage = \"...\")] attribute")
+    })?;
+
+    let location = match location {
+        Some(location) => location,
+        None => u8::try_from(index)
+            .map_err(|_| syn::Error::new(field.span(), "too many vertex attributes"))?,
+    };
+
+    Ok(VertexField {
+        ident: field.ident.clone().unwrap(),
+        ty: field.ty.clone(),
+        usage: quote! { #usage },
+        location,
+        divisor,
+        normalized,
+        integer,
+    })
+}
@@ -0,0 +1,73 @@
+//! Validates shader definitions without a GL context.
+//!
+//! Usage: rs_gl-shaderc [--target gl33|gl4N|gles3|webgl2] <file.shader>...
+//!
+//! Exits with non-zero code if any of the files cannot be loaded or contains invalid GLSL.
+
+use rs_gl::shader::ShaderTarget;
+use rs_gl::shader_validation::validate_shader_file;
+use std::process::ExitCode;
+
+const USAGE: &str = "Usage: rs_gl-shaderc [--target gl33|gl4N|gles3|webgl2] <file.shader>...";
+
+fn parse_target(name: &str) -> Option<ShaderTarget> {
+    match name {
+        "gl33" => Some(ShaderTarget::Gl33Core),
+        "gles3" => Some(ShaderTarget::Gles30),
+        "webgl2" => Some(ShaderTarget::WebGl2),
+        _ => name
+            .strip_prefix("gl4")
+            .and_then(|minor| minor.parse().ok())
+            .map(|minor| ShaderTarget::Gl4 { minor }),
+    }
+}
+
+fn main() -> ExitCode {
+    let mut target = ShaderTarget::default();
+    let mut paths = Vec::new();
+
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        if arg == "--target" {
+            match args.next().as_deref().and_then(parse_target) {
+                Some(t) => target = t,
+                None => {
+                    eprintln!("{}", USAGE);
+                    return ExitCode::from(2);
+                }
+            }
+        } else {
+            paths.push(arg);
+        }
+    }
+
+    if paths.is_empty() {
+        eprintln!("{}", USAGE);
+        return ExitCode::from(2);
+    }
+
+    let mut failed = false;
+    for path in paths.iter() {
+        match validate_shader_file(path, target) {
+            Ok(errors) if errors.is_empty() => {
+                println!("{}: ok", path);
+            }
+            Ok(errors) => {
+                failed = true;
+                for error in errors {
+                    eprintln!("{}: {}", path, error);
+                }
+            }
+            Err(error) => {
+                failed = true;
+                eprintln!("{}: {}", path, error);
+            }
+        }
+    }
+
+    if failed {
+        ExitCode::FAILURE
+    } else {
+        ExitCode::SUCCESS
+    }
+}
@@ -0,0 +1,281 @@
+use glow::HasContext;
+use std::collections::HashSet;
+use crate::vertex_buffer::MAX_VERTEX_SIZE;
+
+/// A profile of the context.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum GlProfile {
+    /// Desktop core profile.
+    Core,
+    /// Desktop compatibility profile.
+    Compatibility,
+    /// OpenGL ES or WebGL.
+    Es,
+}
+
+/// A set of limits and optional features of the context. It is filled once when
+/// [`crate::PipelineState`] is created, other modules consult it before using optional
+/// features.
+#[derive(Clone, Debug)]
+pub struct GlCapabilities {
+    /// Raw `GL_VERSION` string.
+    pub version: String,
+    /// Major version of the context.
+    pub major: u32,
+    /// Minor version of the context.
+    pub minor: u32,
+    /// Profile of the context.
+    pub profile: GlProfile,
+    /// True if the context is WebGL, `profile` is `GlProfile::Es` in this case.
+    pub is_webgl: bool,
+    /// `GL_VENDOR` string.
+    pub vendor: String,
+    /// `GL_RENDERER` string.
+    pub renderer: String,
+    /// Maximum width (and height) of a texture.
+    pub max_texture_size: u32,
+    /// Maximum amount of texture units that can be used at the same time.
+    pub max_texture_units: u32,
+    /// Maximum anisotropy level, 1.0 if anisotropic filtering is not supported.
+    pub max_anisotropy: f32,
+    /// Maximum size of uniform block in bytes.
+    pub max_uniform_block_size: u32,
+    /// Maximum amount of draw buffers (MRT) of a framebuffer.
+    pub max_draw_buffers: u32,
+    /// Maximum distance in bytes between two vertices of an attribute.
+    pub max_vertex_attrib_stride: u32,
+    /// A set of extensions supported by the context.
+    pub extensions: HashSet<String>,
+}
+
+impl GlCapabilities {
+    pub fn from_context(gl: &glow::Context) -> Self {
+        let version = gl.version();
+        let major = version.major;
+        let minor = version.minor;
+
+        unsafe {
+            let profile = if version.is_embedded {
+                GlProfile::Es
+            } else if (major, minor) >= (3, 2)
+                && gl.get_parameter_i32(glow::CONTEXT_PROFILE_MASK) as u32
+                    & glow::CONTEXT_CORE_PROFILE_BIT
+                    != 0
+            {
+                GlProfile::Core
+            } else {
+                GlProfile::Compatibility
+            };
+
+            let extensions = gl.supported_extensions().clone();
+
+            let max_anisotropy = if (!version.is_embedded && (major, minor) >= (4, 6))
+                || has_extension(&extensions, "GL_EXT_texture_filter_anisotropic")
+                || has_extension(&extensions, "GL_ARB_texture_filter_anisotropic")
+            {
+                gl.get_parameter_f32(glow::MAX_TEXTURE_MAX_ANISOTROPY)
+            } else {
+                1.0
+            };
+
+            // The limit is queryable since GL 4.4 and GLES 3.1, older drivers are assumed to
+            // support at least the minimum guaranteed by newer ones.
+            let max_vertex_attrib_stride = if (version.is_embedded && (major, minor) >= (3, 1))
+                || (!version.is_embedded && (major, minor) >= (4, 4))
+            {
+                gl.get_parameter_i32(glow::MAX_VERTEX_ATTRIB_STRIDE) as u32
+            } else {
+                MAX_VERTEX_SIZE as u32
+            };
+
+            let version_string = gl.get_parameter_string(glow::VERSION);
+            let is_webgl = version_string.starts_with("WebGL");
+
+            Self {
+                version: version_string,
+                major,
+                minor,
+                profile,
+                is_webgl,
+                vendor: gl.get_parameter_string(glow::VENDOR),
+                renderer: gl.get_parameter_string(glow::RENDERER),
+                max_texture_size: gl.get_parameter_i32(glow::MAX_TEXTURE_SIZE) as u32,
+                max_texture_units: gl.get_parameter_i32(glow::MAX_COMBINED_TEXTURE_IMAGE_UNITS)
+                    as u32,
+                max_anisotropy,
+                max_uniform_block_size: gl.get_parameter_i32(glow::MAX_UNIFORM_BLOCK_SIZE) as u32,
+                max_draw_buffers: gl.get_parameter_i32(glow::MAX_DRAW_BUFFERS) as u32,
+                max_vertex_attrib_stride,
+                extensions,
+            }
+        }
+    }
+
+    /// Returns true if the context is OpenGL ES or WebGL.
+    pub fn is_gles(&self) -> bool {
+        self.profile == GlProfile::Es
+    }
+
+    /// Returns true if the context version is at least `major.minor`. Desktop and ES versions
+    /// are compared separately, use `is_gles` to tell them apart.
+    pub fn is_version_at_least(&self, major: u32, minor: u32) -> bool {
+        (self.major, self.minor) >= (major, minor)
+    }
+
+    /// Returns true if the extension (for example `GL_ARB_clip_control`) is supported. WebGL
+    /// reports names without `GL_` prefix (`EXT_texture_filter_anisotropic`), so both spellings
+    /// are accepted.
+    pub fn supports_extension(&self, name: &str) -> bool {
+        has_extension(&self.extensions, name)
+    }
+
+    /// Compute shaders: GL 4.3, GLES 3.1 or `GL_ARB_compute_shader`.
+    pub fn supports_compute(&self) -> bool {
+        if self.is_gles() {
+            self.is_version_at_least(3, 1)
+        } else {
+            self.is_version_at_least(4, 3) || self.supports_extension("GL_ARB_compute_shader")
+        }
+    }
+
+    /// Anisotropic texture filtering.
+    pub fn supports_anisotropy(&self) -> bool {
+        self.max_anisotropy > 1.0
+    }
+
+    /// `glPolygonMode`, not available in GLES.
+    pub fn supports_polygon_mode(&self) -> bool {
+        !self.is_gles()
+    }
+
+    /// `GL_DEPTH_CLAMP`: GL 3.2 or `GL_EXT_depth_clamp` on GLES.
+    pub fn supports_depth_clamp(&self) -> bool {
+        if self.is_gles() {
+            self.supports_extension("GL_EXT_depth_clamp")
+        } else {
+            self.is_version_at_least(3, 2) || self.supports_extension("GL_ARB_depth_clamp")
+        }
+    }
+
+    /// `glClipControl`: GL 4.5 or `GL_ARB_clip_control`.
+    pub fn supports_clip_control(&self) -> bool {
+        !self.is_gles()
+            && (self.is_version_at_least(4, 5) || self.supports_extension("GL_ARB_clip_control"))
+    }
+
+    /// Timestamp queries (`glQueryCounter`): GL 3.3 or `GL_ARB_timer_query`. GLES exposes
+    /// them through `GL_EXT_disjoint_timer_query` only, which is not supported.
+    pub fn supports_timer_queries(&self) -> bool {
+        !self.is_gles()
+            && (self.is_version_at_least(3, 3) || self.supports_extension("GL_ARB_timer_query"))
+    }
+
+    /// `GL_TIME_ELAPSED` queries: GL 3.3, `GL_ARB_timer_query`, `GL_EXT_timer_query`,
+    /// `GL_EXT_disjoint_timer_query` on GLES or `EXT_disjoint_timer_query_webgl2` on WebGL.
+    pub fn supports_time_elapsed_queries(&self) -> bool {
+        if self.is_gles() {
+            self.supports_extension("GL_EXT_disjoint_timer_query")
+                || self.supports_extension("EXT_disjoint_timer_query_webgl2")
+        } else {
+            self.supports_timer_queries() || self.supports_extension("GL_EXT_timer_query")
+        }
+    }
+
+    /// `GL_ANY_SAMPLES_PASSED_CONSERVATIVE` queries: GL 4.3, GLES 3.0 or
+    /// `GL_ARB_ES3_compatibility`.
+    pub fn supports_conservative_occlusion_query(&self) -> bool {
+        self.is_gles()
+            || self.is_version_at_least(4, 3)
+            || self.supports_extension("GL_ARB_ES3_compatibility")
+    }
+
+    /// `glBeginConditionalRender`: GL 3.0, not available in GLES.
+    pub fn supports_conditional_render(&self) -> bool {
+        !self.is_gles()
+    }
+
+    /// `glDrawElementsIndirect`: GL 4.0, GLES 3.1 or `GL_ARB_draw_indirect`.
+    pub fn supports_draw_indirect(&self) -> bool {
+        if self.is_gles() {
+            self.is_version_at_least(3, 1)
+        } else {
+            self.is_version_at_least(4, 0) || self.supports_extension("GL_ARB_draw_indirect")
+        }
+    }
+
+    /// `glMultiDrawElementsIndirect`: GL 4.3 or `GL_ARB_multi_draw_indirect`.
+    pub fn supports_multi_draw_indirect(&self) -> bool {
+        !self.is_gles()
+            && (self.is_version_at_least(4, 3)
+                || self.supports_extension("GL_ARB_multi_draw_indirect"))
+    }
+
+    /// Non-zero base vertex in draw calls issued from CPU: GL 3.2, GLES 3.2,
+    /// `GL_ARB_draw_elements_base_vertex` or `GL_OES/EXT_draw_elements_base_vertex`.
+    pub fn supports_base_vertex(&self) -> bool {
+        if self.is_gles() {
+            self.is_version_at_least(3, 2)
+                || self.supports_extension("GL_OES_draw_elements_base_vertex")
+                || self.supports_extension("GL_EXT_draw_elements_base_vertex")
+        } else {
+            self.is_version_at_least(3, 2)
+                || self.supports_extension("GL_ARB_draw_elements_base_vertex")
+        }
+    }
+
+    /// Non-zero base instance in draw calls: GL 4.2 or `GL_ARB_base_instance`.
+    pub fn supports_base_instance(&self) -> bool {
+        !self.is_gles()
+            && (self.is_version_at_least(4, 2) || self.supports_extension("GL_ARB_base_instance"))
+    }
+
+    /// `GL_PRIMITIVE_RESTART_FIXED_INDEX`: GL 4.3, GLES 3.0 or `GL_ARB_ES3_compatibility`.
+    /// Otherwise `GL_PRIMITIVE_RESTART` of GL 3.1 is used.
+    pub fn supports_fixed_index_primitive_restart(&self) -> bool {
+        self.is_gles()
+            || self.is_version_at_least(4, 3)
+            || self.supports_extension("GL_ARB_ES3_compatibility")
+    }
+
+    /// Separate blend state per draw buffer: GL 4.0, GLES 3.2 or `GL_ARB_draw_buffers_blend`.
+    pub fn supports_draw_buffer_blend(&self) -> bool {
+        if self.is_gles() {
+            self.is_version_at_least(3, 2) || self.supports_extension("GL_OES_draw_buffers_indexed")
+        } else {
+            self.is_version_at_least(4, 0) || self.supports_extension("GL_ARB_draw_buffers_blend")
+        }
+    }
+}
+
+// Checks both `GL_`-prefixed and unprefixed spelling of the name.
+fn has_extension(extensions: &HashSet<String>, name: &str) -> bool {
+    let unprefixed = name.strip_prefix("GL_").unwrap_or(name);
+    extensions.contains(unprefixed) || extensions.contains(&format!("GL_{}", unprefixed))
+}
+
+#[cfg(test)]
+mod test {
+    use crate::capabilities::has_extension;
+    use std::collections::HashSet;
+
+    #[test]
+    fn test_extension_spelling() {
+        let extensions = ["GL_ARB_clip_control", "EXT_texture_filter_anisotropic"]
+            .iter()
+            .map(|name| name.to_string())
+            .collect::<HashSet<_>>();
+
+        assert!(has_extension(&extensions, "GL_ARB_clip_control"));
+        assert!(has_extension(&extensions, "ARB_clip_control"));
+        assert!(has_extension(
+            &extensions,
+            "GL_EXT_texture_filter_anisotropic"
+        ));
+        assert!(has_extension(&extensions, "EXT_texture_filter_anisotropic"));
+        assert!(!has_extension(
+            &extensions,
+            "GL_ARB_texture_filter_anisotropic"
+        ));
+        assert!(!has_extension(&extensions, "GL_EXT_clip_control"));
+    }
+}
@@ -1,4 +1,5 @@
 use crate::{
+    indirect_buffer::{DrawElementsIndirectCommand, IndirectBuffer},
     native_buffer::{NativeBuffer,GeometryBufferKind,NativeBufferBuilder},
     pipeline_state::PipelineState,
     surface_data::{SurfaceData},
@@ -18,13 +19,14 @@ use serde::Deserialize;
 
 use crate::core::math::TriangleEdge;
 use crate::native_buffer::FrameworkError;
+use crate::{Log, MessageKind};
 use glow::HasContext;
 
 use crate::{
     core::{math::TriangleDefinition, scope_profile},
 };
 
-use std::{cell::Cell, marker::PhantomData, mem::size_of};
+use std::{cell::Cell, marker::PhantomData, mem::size_of, ops::AddAssign};
 
 pub struct GeometryBuffer {
     state: *mut PipelineState,
@@ -33,6 +35,9 @@ pub struct GeometryBuffer {
     element_buffer_object: glow::Buffer,
     element_count: Cell<usize>,
     element_kind: ElementKind,
+    index_type: Cell<IndexType>,
+    // Index type set by the user, `None` to choose it automatically when indices are set.
+    forced_index_type: Option<IndexType>,
     // Force compiler to not implement Send and Sync, because OpenGL is not thread-safe.
     thread_mark: PhantomData<*const u8>,
 }
@@ -69,10 +74,17 @@ impl GeometryBuffer{
                 .bind_buffer(glow::ELEMENT_ARRAY_BUFFER, Some(self.element_buffer_object));
         }
 
-        GeometryBufferBinding {
+        let mut binding = GeometryBufferBinding {
             state,
             buffer: self,
-        }
+        };
+        binding.apply_primitive_restart();
+        binding
+    }
+
+    /// Type of indices that are currently stored in the element buffer.
+    pub fn index_type(&self) -> IndexType {
+        self.index_type.get()
     }
 }
 
@@ -84,24 +96,91 @@ pub struct GeometryBufferBinding<'a> {
 
 impl<'a> GeometryBufferBinding<'a> {
 
-    unsafe fn set_elements(&self, data: &[u8]) {
+    unsafe fn set_elements(&mut self, data: &[u8]) {
         scope_profile!();
 
+        self.state.frame_statistics.get_mut().bytes_uploaded += data.len();
         self.state
             .gl
             .buffer_data_u8_slice(glow::ELEMENT_ARRAY_BUFFER, data, glow::DYNAMIC_DRAW);
     }
 
-    pub fn set_triangles(self, triangles: &[TriangleDefinition]) -> Self {
+    // Chooses index type and uploads indices. `data` is the same indices as u32 array, it
+    // is uploaded as is if 32-bit indices are used.
+    unsafe fn set_indices(&mut self, indices: impl Iterator<Item = u32> + Clone, data: &[u8]) {
+        let max_index = indices
+            .clone()
+            .filter(|&index| index != PRIMITIVE_RESTART_INDEX)
+            .max()
+            .unwrap_or(0);
+
+        // Maximum value of u16 is reserved for primitive restart.
+        let fits_u16 = max_index < u16::MAX as u32;
+        let index_type = match self.buffer.forced_index_type {
+            Some(IndexType::U16) if !fits_u16 => {
+                Log::writeln(
+                    MessageKind::Warning,
+                    format!(
+                        "Index {} does not fit 16-bit index buffer, 32-bit indices are used.",
+                        max_index
+                    ),
+                );
+                IndexType::U32
+            }
+            Some(index_type) => index_type,
+            None if fits_u16 => IndexType::U16,
+            None => IndexType::U32,
+        };
+        self.buffer.index_type.set(index_type);
+        self.apply_primitive_restart();
+
+        match index_type {
+            IndexType::U16 => {
+                let indices = indices
+                    .map(|index| {
+                        if index == PRIMITIVE_RESTART_INDEX {
+                            u16::MAX
+                        } else {
+                            index as u16
+                        }
+                    })
+                    .collect::<Vec<_>>();
+                self.set_elements(array_as_u8_slice(&indices))
+            }
+            IndexType::U32 => self.set_elements(data),
+        }
+    }
+
+    pub fn set_triangles(mut self, triangles: &[TriangleDefinition]) -> Self {
         scope_profile!();
 
         assert_eq!(self.buffer.element_kind, ElementKind::Triangle);
         self.buffer.element_count.set(triangles.len());
 
-        unsafe { self.set_elements(array_as_u8_slice(triangles)) }
+        unsafe {
+            self.set_indices(
+                triangles.iter().flat_map(|triangle| triangle.0),
+                array_as_u8_slice(triangles),
+            )
+        }
+
+        self
+    }
+
+    /// Sets indices of a strip (`TriangleStrip` or `LineStrip` element kind), strips are
+    /// separated by [`PRIMITIVE_RESTART_INDEX`].
+    pub fn set_strip_indices(mut self, indices: &[u32]) -> Self {
+        scope_profile!();
+
+        assert!(self.buffer.element_kind.is_strip());
+        self.buffer.element_count.set(indices.len());
+
+        unsafe { self.set_indices(indices.iter().copied(), array_as_u8_slice(indices)) }
 
         self
     }
+    /// Draws all elements of the buffer, statistics of the call are also added to the
+    /// statistics of the current frame.
     pub fn draw(&self) -> DrawCallStatistics {
         scope_profile!();
 
@@ -111,30 +190,247 @@ impl<'a> GeometryBufferBinding<'a> {
 
         unsafe { self.draw_internal(start_index, index_count) }
 
+        let statistics = DrawCallStatistics {
+            triangles: self.buffer.element_kind.primitive_count(index_count),
+            instances: 1,
+        };
+        self.state.register_draw_call(statistics);
+        statistics
+    }
+
+    /// Issues a single draw call described by the command: range of indices, instance
+    /// count, base vertex and base instance.
+    pub fn draw_command(
+        &self,
+        command: DrawElementsIndirectCommand,
+    ) -> Result<DrawCallStatistics, FrameworkError> {
+        scope_profile!();
+
+        self.check_command(&command, false)?;
+
+        unsafe { self.draw_command_internal(&command) }
+
+        let statistics = self.command_statistics(&command);
+        self.state.register_draw_call(statistics);
+        Ok(statistics)
+    }
+
+    /// Draws a single command of the indirect buffer. Parameters of the command are read by
+    /// GPU if indirect rendering is supported, otherwise the command is issued from CPU.
+    pub fn draw_indirect(
+        &self,
+        indirect_buffer: &IndirectBuffer,
+        command_index: usize,
+    ) -> Result<DrawCallStatistics, FrameworkError> {
+        scope_profile!();
+
+        let command = *indirect_buffer.commands().get(command_index).ok_or(
+            FrameworkError::InvalidElementRange {
+                start: command_index,
+                end: command_index + 1,
+                total: indirect_buffer.len(),
+            },
+        )?;
+
+        if !indirect_buffer.is_gpu_resident() {
+            return self.draw_command(command);
+        }
+
+        self.check_command(&command, true)?;
+
+        if command.count > 0 {
+            unsafe {
+                self.state.validate_state();
+
+                indirect_buffer.bind(self.state);
+                self.state.gl.draw_elements_indirect_offset(
+                    self.mode(),
+                    self.buffer.index_type.get().gl_type(),
+                    (command_index * size_of::<DrawElementsIndirectCommand>()) as i32,
+                );
+            }
+        }
+
+        let statistics = self.command_statistics(&command);
+        self.state.register_draw_call(statistics);
+        Ok(statistics)
+    }
+
+    /// Draws every command of the indirect buffer, in one call on GL 4.3+ or one call per
+    /// command otherwise (always on WebGL).
+    pub fn multi_draw_indirect(
+        &self,
+        indirect_buffer: &IndirectBuffer,
+    ) -> Result<DrawCallStatistics, FrameworkError> {
+        scope_profile!();
+
+        let mut statistics = DrawCallStatistics::default();
+
+        // Multi draw is called through raw native functions, it does not exist in WebGL.
+        if cfg!(target_arch = "wasm32")
+            || !indirect_buffer.is_gpu_resident()
+            || !self.state.capabilities().supports_multi_draw_indirect()
+        {
+            for index in 0..indirect_buffer.len() {
+                statistics += self.draw_indirect(indirect_buffer, index)?;
+            }
+            return Ok(statistics);
+        }
+
+        #[cfg(not(target_arch = "wasm32"))]
+        {
+            for command in indirect_buffer.commands() {
+                self.check_command(command, true)?;
+                statistics += self.command_statistics(command);
+            }
+
+            if !indirect_buffer.is_empty() {
+                unsafe {
+                    self.state.validate_state();
+
+                    indirect_buffer.bind(self.state);
+                    self.state.gl.raw.MultiDrawElementsIndirect(
+                        self.mode(),
+                        self.buffer.index_type.get().gl_type(),
+                        std::ptr::null(),
+                        indirect_buffer.len() as i32,
+                        // Commands are tightly packed.
+                        0,
+                    );
+                }
+
+                self.state.register_draw_call(statistics);
+            }
+        }
+
+        Ok(statistics)
+    }
+
+    // Checks that the command does not read indices outside of the element buffer and does
+    // not use features that are not supported. Commands read by GPU support base vertex
+    // natively, commands issued from CPU need base vertex draw calls.
+    fn check_command(
+        &self,
+        command: &DrawElementsIndirectCommand,
+        gpu_resident: bool,
+    ) -> Result<(), FrameworkError> {
+        let total = self.buffer.element_count.get() * self.buffer.element_kind.index_per_element();
+        let start = command.first_index as usize;
+        let end = start + command.count as usize;
+        if end > total {
+            return Err(FrameworkError::InvalidElementRange { start, end, total });
+        }
+
+        if command.base_instance != 0 && !self.state.capabilities().supports_base_instance() {
+            return Err(FrameworkError::UnsupportedFeature(
+                "Base instance".to_owned(),
+            ));
+        }
+
+        if !gpu_resident
+            && command.base_vertex != 0
+            && !self.state.capabilities().supports_base_vertex()
+        {
+            return Err(FrameworkError::UnsupportedFeature(
+                "Base vertex".to_owned(),
+            ));
+        }
+
+        Ok(())
+    }
+
+    fn command_statistics(&self, command: &DrawElementsIndirectCommand) -> DrawCallStatistics {
         DrawCallStatistics {
-            triangles: self.buffer.element_count.get(),
+            triangles: self
+                .buffer
+                .element_kind
+                .primitive_count(command.count as usize)
+                * command.instance_count as usize,
+            instances: command.instance_count as usize,
         }
     }
 
+    unsafe fn draw_command_internal(&self, command: &DrawElementsIndirectCommand) {
+        if command.count == 0 || command.instance_count == 0 {
+            return;
+        }
+
+        self.state.validate_state();
+
+        let index_type = self.buffer.index_type.get();
+        let offset = (command.first_index as usize * index_type.size()) as i32;
+        if command.base_vertex == 0 && command.base_instance == 0 {
+            // Base vertex entry points are not available on GLES 3.0/3.1 and WebGL 2.
+            self.state.gl.draw_elements_instanced(
+                self.mode(),
+                command.count as i32,
+                index_type.gl_type(),
+                offset,
+                command.instance_count as i32,
+            );
+        } else if command.base_instance != 0 {
+            self.state
+                .gl
+                .draw_elements_instanced_base_vertex_base_instance(
+                    self.mode(),
+                    command.count as i32,
+                    index_type.gl_type(),
+                    offset,
+                    command.instance_count as i32,
+                    command.base_vertex,
+                    command.base_instance,
+                );
+        } else {
+            self.state.gl.draw_elements_instanced_base_vertex(
+                self.mode(),
+                command.count as i32,
+                index_type.gl_type(),
+                offset,
+                command.instance_count as i32,
+                command.base_vertex,
+            );
+        }
+    }
+
+    pub(crate) fn state(&mut self) -> &mut PipelineState {
+        self.state
+    }
+
     unsafe fn draw_internal(&self, start_index: usize, index_count: usize) {
         scope_profile!();
 
         if index_count > 0 {
-            let indices = (start_index * size_of::<u32>()) as i32;
+            self.state.validate_state();
+
+            let index_type = self.buffer.index_type.get();
+            let indices = (start_index * index_type.size()) as i32;
             self.state.gl.draw_elements(
                 self.mode(),
                 index_count as i32,
-                glow::UNSIGNED_INT,
+                index_type.gl_type(),
                 indices,
             );
         }
     }
 
+    // Strips are separated by the maximum value of the index type. Applied when the buffer
+    // is bound and when its index type changes, so draw calls need only a shared reference.
+    fn apply_primitive_restart(&mut self) {
+        let restart_index = if self.buffer.element_kind.is_strip() {
+            Some(self.buffer.index_type.get().restart_index())
+        } else {
+            None
+        };
+        self.state.set_primitive_restart(restart_index);
+    }
+
 
     fn mode(&self) -> u32 {
         match self.buffer.element_kind {
             ElementKind::Triangle => glow::TRIANGLES,
             ElementKind::Line => glow::LINES,
+            ElementKind::TriangleStrip => glow::TRIANGLE_STRIP,
+            ElementKind::LineStrip => glow::LINE_STRIP,
         }
     }
 }
@@ -144,6 +440,10 @@ impl<'a> GeometryBufferBinding<'a> {
 pub enum ElementKind {
     Triangle,
     Line,
+    /// Strips of triangles separated by [`PRIMITIVE_RESTART_INDEX`].
+    TriangleStrip,
+    /// Strips of lines separated by [`PRIMITIVE_RESTART_INDEX`].
+    LineStrip,
 }
 
 impl ElementKind {
@@ -151,6 +451,56 @@ impl ElementKind {
         match self {
             ElementKind::Triangle => 3,
             ElementKind::Line => 2,
+            ElementKind::TriangleStrip | ElementKind::LineStrip => 1,
+        }
+    }
+
+    fn is_strip(self) -> bool {
+        matches!(self, ElementKind::TriangleStrip | ElementKind::LineStrip)
+    }
+
+    // Amount of primitives drawn from given amount of indices, restarts are not counted.
+    fn primitive_count(self, index_count: usize) -> usize {
+        match self {
+            ElementKind::Triangle => index_count / 3,
+            ElementKind::Line => index_count / 2,
+            ElementKind::TriangleStrip => index_count.saturating_sub(2),
+            ElementKind::LineStrip => index_count.saturating_sub(1),
+        }
+    }
+}
+
+/// An index that separates strips, it is replaced with the maximum value of actual index
+/// type when indices are uploaded.
+pub const PRIMITIVE_RESTART_INDEX: u32 = u32::MAX;
+
+/// Type of indices in the element buffer. 16-bit indices take half of the memory, but
+/// can address only 65535 vertices.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum IndexType {
+    U16,
+    U32,
+}
+
+impl IndexType {
+    pub fn size(self) -> usize {
+        match self {
+            IndexType::U16 => size_of::<u16>(),
+            IndexType::U32 => size_of::<u32>(),
+        }
+    }
+
+    fn gl_type(self) -> u32 {
+        match self {
+            IndexType::U16 => glow::UNSIGNED_SHORT,
+            IndexType::U32 => glow::UNSIGNED_INT,
+        }
+    }
+
+    fn restart_index(self) -> u32 {
+        match self {
+            IndexType::U16 => u16::MAX as u32,
+            IndexType::U32 => u32::MAX,
         }
     }
 }
@@ -159,23 +509,40 @@ impl ElementKind {
 #[derive(Debug, Copy, Clone, Default)]
 pub struct DrawCallStatistics {
     pub triangles: usize,
+    pub instances: usize,
+}
+
+impl AddAssign for DrawCallStatistics {
+    fn add_assign(&mut self, rhs: Self) {
+        self.triangles += rhs.triangles;
+        self.instances += rhs.instances;
+    }
 }
 
 
-pub struct GeometryBufferBuilder {
+pub struct GeometryBufferBuilder<'a> {
     element_kind: ElementKind,
-    buffers: Vec<NativeBufferBuilder>,
+    buffers: Vec<NativeBufferBuilder<'a>>,
+    index_type: Option<IndexType>,
 }
 
-impl GeometryBufferBuilder {
+impl<'a> GeometryBufferBuilder<'a> {
     pub fn new(element_kind: ElementKind) -> Self {
         Self {
             element_kind,
             buffers: Default::default(),
+            index_type: None,
         }
     }
 
-    pub fn with_buffer_builder(mut self, builder: NativeBufferBuilder) -> Self {
+    /// Forces type of indices. By default 16-bit indices are used if all indices fit them.
+    /// Forced 16-bit type is ignored if indices do not fit it.
+    pub fn with_index_type(mut self, index_type: IndexType) -> Self {
+        self.index_type = Some(index_type);
+        self
+    }
+
+    pub fn with_buffer_builder(mut self, builder: NativeBufferBuilder<'a>) -> Self {
         self.buffers.push(builder);
         self
     }
@@ -200,6 +567,8 @@ impl GeometryBufferBuilder {
             element_buffer_object: ebo,
             element_count: Cell::new(0),
             element_kind: self.element_kind,
+            index_type: Cell::new(self.index_type.unwrap_or(IndexType::U32)),
+            forced_index_type: self.index_type,
             thread_mark: PhantomData,
         })
     }
@@ -215,6 +584,45 @@ pub struct DrawParameters {
     pub depth_test: bool,
     pub blend: Option<BlendFunc>,
     pub stencil_op: StencilOp,
+    #[visit(optional)]
+    #[serde(default)]
+    pub depth_func: CompareFunc,
+    #[visit(optional)]
+    #[serde(default)]
+    pub polygon_mode: PolygonFillMode,
+    #[visit(optional)]
+    #[serde(default)]
+    pub polygon_offset: Option<PolygonOffset>,
+    #[visit(optional)]
+    #[serde(default = "default_line_width")]
+    pub line_width: f32,
+    #[visit(optional)]
+    #[serde(default)]
+    pub depth_clamp: bool,
+    #[visit(optional)]
+    #[serde(default)]
+    pub depth_range: DepthRange,
+    #[visit(optional)]
+    #[serde(default)]
+    pub clip_depth: ClipDepth,
+    /// Constant color used by `ConstantColor`/`ConstantAlpha` blend factors.
+    #[visit(optional)]
+    #[serde(default)]
+    pub blend_color: Vector4<f32>,
+    /// Blend state of each draw buffer (MRT), index in the list is the index of draw buffer.
+    /// Draw buffers that are not in the list use `blend`. Empty list means `blend` for all.
+    #[visit(optional)]
+    #[serde(default)]
+    pub draw_buffer_blend: Vec<Option<BlendFunc>>,
+    /// Stencil state for back-facing polygons. When set, `stencil_test` and `stencil_op`
+    /// apply to front-facing polygons only and stencil test is enabled for both faces.
+    #[visit(optional)]
+    #[serde(default)]
+    pub back_stencil: Option<BackFaceStencil>,
+}
+
+fn default_line_width() -> f32 {
+    1.0
 }
 
 impl Default for DrawParameters {
@@ -227,11 +635,83 @@ impl Default for DrawParameters {
             depth_test: true,
             blend: None,
             stencil_op: Default::default(),
+            depth_func: Default::default(),
+            polygon_mode: Default::default(),
+            polygon_offset: None,
+            line_width: default_line_width(),
+            depth_clamp: false,
+            depth_range: Default::default(),
+            clip_depth: Default::default(),
+            blend_color: Default::default(),
+            draw_buffer_blend: Default::default(),
+            back_stencil: None,
         }
     }
 }
 
 
+#[derive(Copy, Clone, PartialOrd, PartialEq, Eq, Hash, Debug, Deserialize, Visit)]
+#[repr(u32)]
+pub enum PolygonFillMode {
+    /// Only vertices of polygons are rasterized.
+    Point = glow::POINT,
+    /// Only edges of polygons are rasterized (wireframe).
+    Line = glow::LINE,
+    /// Whole polygon is rasterized.
+    Fill = glow::FILL,
+}
+
+impl Default for PolygonFillMode {
+    fn default() -> Self {
+        Self::Fill
+    }
+}
+
+
+/// Depth offset applied to rasterized polygons, `factor * DZ + units * r`, where `DZ` is
+/// depth slope of a polygon and `r` is minimal resolvable depth difference. Used for
+/// decals and shadow bias.
+#[derive(Copy, Clone, PartialOrd, PartialEq, Debug, Deserialize, Visit, Default)]
+pub struct PolygonOffset {
+    pub factor: f32,
+    pub units: f32,
+}
+
+
+/// Mapping of normalized device depth to window depth.
+#[derive(Copy, Clone, PartialOrd, PartialEq, Debug, Deserialize, Visit)]
+pub struct DepthRange {
+    pub near: f32,
+    pub far: f32,
+}
+
+impl Default for DepthRange {
+    fn default() -> Self {
+        Self {
+            near: 0.0,
+            far: 1.0,
+        }
+    }
+}
+
+
+/// Clip space depth convention. `ZeroToOne` together with `CompareFunc::Greater` and
+/// clear depth of 0.0 gives reversed-Z. It requires `glClipControl` (GL 4.5 or
+/// `GL_ARB_clip_control`) and falls back to `NegativeOneToOne` when it is not available.
+#[derive(Copy, Clone, PartialOrd, PartialEq, Eq, Hash, Debug, Deserialize, Visit)]
+#[repr(u32)]
+pub enum ClipDepth {
+    NegativeOneToOne = glow::NEGATIVE_ONE_TO_ONE,
+    ZeroToOne = glow::ZERO_TO_ONE,
+}
+
+impl Default for ClipDepth {
+    fn default() -> Self {
+        Self::NegativeOneToOne
+    }
+}
+
+
 #[derive(Copy, Clone, PartialOrd, PartialEq, Hash, Debug, Deserialize, Visit)]
 #[repr(u32)]
 pub enum CullFace {
@@ -295,6 +775,13 @@ impl Default for StencilFunc {
 }
 
 
+#[derive(Copy, Clone, PartialOrd, PartialEq, Hash, Debug, Deserialize, Visit, Default)]
+pub struct BackFaceStencil {
+    pub func: StencilFunc,
+    pub op: StencilOp,
+}
+
+
 #[derive(Copy, Clone, PartialOrd, PartialEq, Eq, Ord, Hash, Visit, Deserialize, Debug)]
 #[repr(u32)]
 pub enum CompareFunc {
@@ -334,15 +821,92 @@ impl Default for CompareFunc {
 pub struct BlendFunc {
     pub sfactor: BlendFactor,
     pub dfactor: BlendFactor,
+    /// Source factor for alpha channel, `None` means the same as `sfactor`.
+    #[serde(default)]
+    pub alpha_sfactor: Option<BlendFactor>,
+    /// Destination factor for alpha channel, `None` means the same as `dfactor`.
+    #[serde(default)]
+    pub alpha_dfactor: Option<BlendFactor>,
+    #[serde(default)]
+    pub equation: BlendEquation,
+    /// Equation for alpha channel, `None` means the same as `equation`.
+    #[serde(default)]
+    pub alpha_equation: Option<BlendEquation>,
 }
 
 impl Default for BlendFunc {
     fn default() -> Self {
+        Self::new(BlendFactor::One, BlendFactor::Zero)
+    }
+}
+
+impl BlendFunc {
+    /// Creates blend function with the same factors for color and alpha and `Add` equation.
+    pub fn new(sfactor: BlendFactor, dfactor: BlendFactor) -> Self {
+        Self {
+            sfactor,
+            dfactor,
+            alpha_sfactor: None,
+            alpha_dfactor: None,
+            equation: BlendEquation::Add,
+            alpha_equation: None,
+        }
+    }
+
+    /// Creates blend function with separate factors for color and alpha.
+    pub fn new_separate(
+        sfactor: BlendFactor,
+        dfactor: BlendFactor,
+        alpha_sfactor: BlendFactor,
+        alpha_dfactor: BlendFactor,
+    ) -> Self {
         Self {
-            sfactor: BlendFactor::One,
-            dfactor: BlendFactor::Zero,
+            alpha_sfactor: Some(alpha_sfactor),
+            alpha_dfactor: Some(alpha_dfactor),
+            ..Self::new(sfactor, dfactor)
         }
     }
+
+    /// Sets equations for color and alpha.
+    pub fn with_equation(mut self, equation: BlendEquation, alpha_equation: BlendEquation) -> Self {
+        self.equation = equation;
+        self.alpha_equation = Some(alpha_equation);
+        self
+    }
+
+    pub fn alpha_sfactor(&self) -> BlendFactor {
+        self.alpha_sfactor.unwrap_or(self.sfactor)
+    }
+
+    pub fn alpha_dfactor(&self) -> BlendFactor {
+        self.alpha_dfactor.unwrap_or(self.dfactor)
+    }
+
+    pub fn alpha_equation(&self) -> BlendEquation {
+        self.alpha_equation.unwrap_or(self.equation)
+    }
+}
+
+
+#[derive(Copy, Clone, Hash, PartialOrd, PartialEq, Eq, Ord, Deserialize, Visit, Debug)]
+#[repr(u32)]
+pub enum BlendEquation {
+    /// `src * sfactor + dst * dfactor`
+    Add = glow::FUNC_ADD,
+    /// `src * sfactor - dst * dfactor`
+    Subtract = glow::FUNC_SUBTRACT,
+    /// `dst * dfactor - src * sfactor`
+    ReverseSubtract = glow::FUNC_REVERSE_SUBTRACT,
+    /// `min(src, dst)`, factors are ignored.
+    Min = glow::MIN,
+    /// `max(src, dst)`, factors are ignored.
+    Max = glow::MAX,
+}
+
+impl Default for BlendEquation {
+    fn default() -> Self {
+        Self::Add
+    }
 }
 
 
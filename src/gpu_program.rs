@@ -7,6 +7,8 @@ use nalgebra::Matrix2;
 use nalgebra::Matrix3;
 use nalgebra::Matrix4;
 use crate::core::color::Color;
+use crate::shader::ShaderTarget;
+use crate::pipeline_state::PipelineStatistics;
 use crate::{
     GpuTexture,
     PipelineState,
@@ -18,6 +20,7 @@ use crate::{
     MessageKind,
     Log,
 };
+use fyrox::utils::{array_as_u8_slice, value_as_u8_slice};
 use glow::HasContext;
 
 pub struct GpuProgram {
@@ -26,6 +29,9 @@ pub struct GpuProgram {
     // Force compiler to not implement Send and Sync, because OpenGL is not thread-safe.
     thread_mark: PhantomData<*const u8>,
     uniform_locations: RefCell<FxHashMap<ImmutableString, Option<UniformLocation>>>,
+    // Last values written to uniforms of the program. Uniform values are part of the
+    // program object, so they survive program switches and can be compared across draws.
+    uniform_values: RefCell<UniformValueCache>,
     pub(crate) built_in_uniform_locations:
         [Option<UniformLocation>; BuiltInUniform::Count as usize],
 }
@@ -81,12 +87,17 @@ impl GpuProgram{
 
                 Log::writeln(MessageKind::Information, msg);
 
+                let mut uniform_locations = Default::default();
+                let built_in_uniform_locations =
+                    fetch_built_in_uniform_locations(state, program, &mut uniform_locations);
+
                 Ok(Self {
                     state,
                     id: program,
                     thread_mark: PhantomData,
-                    uniform_locations: Default::default(),
-                    built_in_uniform_locations: fetch_built_in_uniform_locations(state, program),
+                    uniform_locations: RefCell::new(uniform_locations),
+                    uniform_values: Default::default(),
+                    built_in_uniform_locations,
                 })
             }
         }
@@ -97,17 +108,12 @@ impl GpuProgram{
         state: &PipelineState,
         name: &ImmutableString,
     ) -> Option<UniformLocation> {
-        let mut locations = self.uniform_locations.borrow_mut();
-
-        if let Some(cached_location) = locations.get(name) {
-            cached_location.clone()
-        } else {
-            let location = fetch_uniform_location(state, self.id, name.deref());
-
-            locations.insert(name.clone(), location.clone());
-
-            location
-        }
+        cached_uniform_location(
+            state,
+            self.id,
+            &mut self.uniform_locations.borrow_mut(),
+            name,
+        )
     }
 
     pub fn bind<'a, 'b>(&'b self, state: &'a mut PipelineState) -> GpuProgramBinding<'a, 'b> {
@@ -123,11 +129,106 @@ impl GpuProgram{
 #[derive(Clone, Debug)]
 pub struct UniformLocation {
     id: glow::UniformLocation,
+    // Index of the uniform in the value cache of its program. Uniform locations cannot be
+    // used as keys, web backend does not implement `Hash` for them.
+    cache_index: usize,
     // Force compiler to not implement Send and Sync, because OpenGL is not thread-safe.
     thread_mark: PhantomData<*const u8>,
 }
 
 
+// Values up to the size of a 4x4 matrix are stored inline, larger ones (arrays) are stored
+// on heap. Both are compared byte-by-byte, so a changed value is never skipped.
+const MAX_INLINE_UNIFORM_SIZE: usize = 64;
+
+#[derive(Clone)]
+enum CachedUniformValue {
+    Inline {
+        len: usize,
+        bytes: [u8; MAX_INLINE_UNIFORM_SIZE],
+    },
+    Heap(Vec<u8>),
+}
+
+impl CachedUniformValue {
+    fn new(value: &[u8]) -> Self {
+        if value.len() <= MAX_INLINE_UNIFORM_SIZE {
+            let mut bytes = [0; MAX_INLINE_UNIFORM_SIZE];
+            bytes[..value.len()].copy_from_slice(value);
+            Self::Inline {
+                len: value.len(),
+                bytes,
+            }
+        } else {
+            Self::Heap(value.to_vec())
+        }
+    }
+
+    // Replaces the value, reusing heap buffer if possible.
+    fn set(&mut self, value: &[u8]) {
+        match self {
+            Self::Heap(bytes) if value.len() > MAX_INLINE_UNIFORM_SIZE => {
+                bytes.clear();
+                bytes.extend_from_slice(value);
+            }
+            _ => *self = Self::new(value),
+        }
+    }
+
+    fn matches(&self, value: &[u8]) -> bool {
+        match self {
+            Self::Inline { len, bytes } => &bytes[..*len] == value,
+            Self::Heap(bytes) => bytes.as_slice() == value,
+        }
+    }
+}
+
+// Last values written to uniforms of a program, indexed by `UniformLocation::cache_index`.
+#[derive(Default)]
+struct UniformValueCache {
+    values: Vec<Option<CachedUniformValue>>,
+}
+
+impl UniformValueCache {
+    // Remembers given value as the last one written to the uniform. Returns `false` if the
+    // uniform already holds exactly the same value, so the upload can be skipped.
+    fn update(&mut self, index: usize, bytes: &[u8]) -> bool {
+        if index >= self.values.len() {
+            self.values.resize(index + 1, None);
+        }
+
+        match &mut self.values[index] {
+            Some(cached) if cached.matches(bytes) => false,
+            Some(cached) => {
+                cached.set(bytes);
+                true
+            }
+            slot => {
+                *slot = Some(CachedUniformValue::new(bytes));
+                true
+            }
+        }
+    }
+
+    // Same as `update`, but also counts uploaded and skipped values in the statistics.
+    fn begin_upload(
+        &mut self,
+        statistics: &mut PipelineStatistics,
+        index: usize,
+        bytes: &[u8],
+    ) -> bool {
+        let changed = self.update(index, bytes);
+        if changed {
+            statistics.uniform_uploads += 1;
+            statistics.bytes_uploaded += bytes.len();
+        } else {
+            statistics.uniform_uploads_skipped += 1;
+        }
+        changed
+    }
+}
+
+
 #[repr(usize)]
 pub enum BuiltInUniform {
     WorldMatrix,
@@ -149,7 +250,7 @@ unsafe fn create_shader(
     actual_type: u32,
     source: &str,
 ) -> Result<glow::Shader, FrameworkError> {
-    let merged_source = prepare_source_code(source);
+    let merged_source = prepare_source_code(source, state.shader_target());
 
     let shader = state.gl.create_shader(actual_type)?;
     state.gl.shader_source(shader, &merged_source);
@@ -186,65 +287,66 @@ unsafe fn create_shader(
 }
 
 
-#[allow(clippy::let_and_return)]
-fn prepare_source_code(code: &str) -> String {
-    let mut full_source_code = "#version 330 core\n// include 'shared.glsl'\n".to_owned();
-
-    // HACK
-    #[cfg(target_arch = "wasm32")]
-    {
-        full_source_code += r#"    
-            precision highp float;
-            precision lowp usampler2D;
-            precision lowp sampler3D;
-        "#;
-    }
-
+pub(crate) fn prepare_source_code(code: &str, target: ShaderTarget) -> String {
+    let mut full_source_code = target.header();
+    full_source_code += "// include 'shared.glsl'\n";
     full_source_code += include_str!("shaders/shared.glsl");
     full_source_code += "\n// end of include\n";
     full_source_code += code;
-
-    // HACK
-    #[cfg(target_arch = "wasm32")]
-    {
-        full_source_code.replace("#version 330 core", "#version 300 es")
-    }
-
-    #[cfg(not(target_arch = "wasm32"))]
     full_source_code
 }
 
 
+// Built-in uniforms are registered in the same name map as the others, so a built-in
+// uniform fetched by name shares the cached value with its built-in location.
 fn fetch_built_in_uniform_locations(
     state: &PipelineState,
     program: glow::Program,
+    uniform_locations: &mut FxHashMap<ImmutableString, Option<UniformLocation>>,
 ) -> [Option<UniformLocation>; BuiltInUniform::Count as usize] {
     const INIT: Option<UniformLocation> = None;
     let mut locations = [INIT; BuiltInUniform::Count as usize];
 
-    locations[BuiltInUniform::WorldMatrix as usize] =
-        fetch_uniform_location(state, program, "fyrox_worldMatrix");
+    let mut fetch = |name: &str| {
+        cached_uniform_location(state, program, uniform_locations, &ImmutableString::new(name))
+    };
+
+    locations[BuiltInUniform::WorldMatrix as usize] = fetch("fyrox_worldMatrix");
     locations[BuiltInUniform::WorldViewProjectionMatrix as usize] =
-        fetch_uniform_location(state, program, "fyrox_worldViewProjection");
-    locations[BuiltInUniform::BoneMatrices as usize] =
-        fetch_uniform_location(state, program, "fyrox_boneMatrices");
+        fetch("fyrox_worldViewProjection");
+    locations[BuiltInUniform::BoneMatrices as usize] = fetch("fyrox_boneMatrices");
     locations[BuiltInUniform::UseSkeletalAnimation as usize] =
-        fetch_uniform_location(state, program, "fyrox_useSkeletalAnimation");
-    locations[BuiltInUniform::CameraPosition as usize] =
-        fetch_uniform_location(state, program, "fyrox_cameraPosition");
-    locations[BuiltInUniform::UsePOM as usize] =
-        fetch_uniform_location(state, program, "fyrox_usePOM");
-    locations[BuiltInUniform::LightPosition as usize] =
-        fetch_uniform_location(state, program, "fyrox_lightPosition");
+        fetch("fyrox_useSkeletalAnimation");
+    locations[BuiltInUniform::CameraPosition as usize] = fetch("fyrox_cameraPosition");
+    locations[BuiltInUniform::UsePOM as usize] = fetch("fyrox_usePOM");
+    locations[BuiltInUniform::LightPosition as usize] = fetch("fyrox_lightPosition");
 
     locations
 }
 
+// Every name gets its own slot of the value cache (even if there is no such uniform), so
+// the amount of names fetched so far is the index of the next slot.
+fn cached_uniform_location(
+    state: &PipelineState,
+    program: glow::Program,
+    uniform_locations: &mut FxHashMap<ImmutableString, Option<UniformLocation>>,
+    name: &ImmutableString,
+) -> Option<UniformLocation> {
+    if let Some(cached_location) = uniform_locations.get(name) {
+        return cached_location.clone();
+    }
+
+    let cache_index = uniform_locations.len();
+    let location = fetch_uniform_location(state, program, name.deref(), cache_index);
+    uniform_locations.insert(name.clone(), location.clone());
+    location
+}
 
 fn fetch_uniform_location(
     state: &PipelineState,
     program: glow::Program,
     id: &str,
+    cache_index: usize,
 ) -> Option<UniformLocation> {
     unsafe {
         state
@@ -252,6 +354,7 @@ fn fetch_uniform_location(
             .get_uniform_location(program, id)
             .map(|id| UniformLocation {
                 id,
+                cache_index,
                 thread_mark: PhantomData,
             })
     }
@@ -269,17 +372,27 @@ impl<'a, 'b> GpuProgramBinding<'a, 'b> {
         self.program.uniform_location_internal(self.state, name)
     }
 
+    // Returns true if the value differs from the one that was written last time, and so
+    // it must be uploaded. Updates uniform statistics of the pipeline.
+    #[inline(always)]
+    fn begin_upload(&mut self, location: &UniformLocation, bytes: &[u8]) -> bool {
+        self.program.uniform_values.borrow_mut().begin_upload(
+            self.state.frame_statistics.get_mut(),
+            location.cache_index,
+            bytes,
+        )
+    }
+
     #[inline(always)]
     pub fn set_texture(
         &mut self,
         location: &UniformLocation,
         texture: &Rc<RefCell<GpuTexture>>,
     ) -> &mut Self {
-        unsafe {
-            self.state
-                .gl
-                .uniform_1_i32(Some(&location.id), self.active_sampler as i32)
-        };
+        let sampler = self.active_sampler as i32;
+        if self.begin_upload(location, value_as_u8_slice(&sampler)) {
+            unsafe { self.state.gl.uniform_1_i32(Some(&location.id), sampler) };
+        }
         texture.borrow().bind(self.state, self.active_sampler);
         self.active_sampler += 1;
         self
@@ -287,89 +400,107 @@ impl<'a, 'b> GpuProgramBinding<'a, 'b> {
 
     #[inline(always)]
     pub fn set_bool(&mut self, location: &UniformLocation, value: bool) -> &mut Self {
-        unsafe {
-            self.state.gl.uniform_1_i32(
-                Some(&location.id),
-                if value { glow::TRUE } else { glow::FALSE } as i32,
-            );
+        let value = if value { glow::TRUE } else { glow::FALSE } as i32;
+        if self.begin_upload(location, value_as_u8_slice(&value)) {
+            unsafe {
+                self.state.gl.uniform_1_i32(Some(&location.id), value);
+            }
         }
         self
     }
 
     #[inline(always)]
     pub fn set_i32(&mut self, location: &UniformLocation, value: i32) -> &mut Self {
-        unsafe {
-            self.state.gl.uniform_1_i32(Some(&location.id), value);
+        if self.begin_upload(location, value_as_u8_slice(&value)) {
+            unsafe {
+                self.state.gl.uniform_1_i32(Some(&location.id), value);
+            }
         }
         self
     }
 
     #[inline(always)]
     pub fn set_u32(&mut self, location: &UniformLocation, value: u32) -> &mut Self {
-        unsafe {
-            self.state.gl.uniform_1_u32(Some(&location.id), value);
+        if self.begin_upload(location, value_as_u8_slice(&value)) {
+            unsafe {
+                self.state.gl.uniform_1_u32(Some(&location.id), value);
+            }
         }
         self
     }
 
     #[inline(always)]
     pub fn set_f32(&mut self, location: &UniformLocation, value: f32) -> &mut Self {
-        unsafe {
-            self.state.gl.uniform_1_f32(Some(&location.id), value);
+        if self.begin_upload(location, value_as_u8_slice(&value)) {
+            unsafe {
+                self.state.gl.uniform_1_f32(Some(&location.id), value);
+            }
         }
         self
     }
 
     #[inline(always)]
     pub fn set_vector2(&mut self, location: &UniformLocation, value: &Vector2<f32>) -> &mut Self {
-        unsafe {
-            self.state
-                .gl
-                .uniform_2_f32(Some(&location.id), value.x, value.y);
+        if self.begin_upload(location, value_as_u8_slice(value)) {
+            unsafe {
+                self.state
+                    .gl
+                    .uniform_2_f32(Some(&location.id), value.x, value.y);
+            }
         }
         self
     }
 
     #[inline(always)]
     pub fn set_vector3(&mut self, location: &UniformLocation, value: &Vector3<f32>) -> &mut Self {
-        unsafe {
-            self.state
-                .gl
-                .uniform_3_f32(Some(&location.id), value.x, value.y, value.z);
+        if self.begin_upload(location, value_as_u8_slice(value)) {
+            unsafe {
+                self.state
+                    .gl
+                    .uniform_3_f32(Some(&location.id), value.x, value.y, value.z);
+            }
         }
         self
     }
 
     #[inline(always)]
     pub fn set_vector4(&mut self, location: &UniformLocation, value: &Vector4<f32>) -> &mut Self {
-        unsafe {
-            self.state
-                .gl
-                .uniform_4_f32(Some(&location.id), value.x, value.y, value.z, value.w);
+        if self.begin_upload(location, value_as_u8_slice(value)) {
+            unsafe {
+                self.state
+                    .gl
+                    .uniform_4_f32(Some(&location.id), value.x, value.y, value.z, value.w);
+            }
         }
         self
     }
 
     #[inline(always)]
     pub fn set_i32_slice(&mut self, location: &UniformLocation, value: &[i32]) -> &mut Self {
-        unsafe {
-            self.state.gl.uniform_1_i32_slice(Some(&location.id), value);
+        if self.begin_upload(location, array_as_u8_slice(value)) {
+            unsafe {
+                self.state.gl.uniform_1_i32_slice(Some(&location.id), value);
+            }
         }
         self
     }
 
     #[inline(always)]
     pub fn set_u32_slice(&mut self, location: &UniformLocation, value: &[u32]) -> &mut Self {
-        unsafe {
-            self.state.gl.uniform_1_u32_slice(Some(&location.id), value);
+        if self.begin_upload(location, array_as_u8_slice(value)) {
+            unsafe {
+                self.state.gl.uniform_1_u32_slice(Some(&location.id), value);
+            }
         }
         self
     }
 
     #[inline(always)]
     pub fn set_f32_slice(&mut self, location: &UniformLocation, value: &[f32]) -> &mut Self {
-        unsafe {
-            self.state.gl.uniform_1_f32_slice(Some(&location.id), value);
+        if self.begin_upload(location, array_as_u8_slice(value)) {
+            unsafe {
+                self.state.gl.uniform_1_f32_slice(Some(&location.id), value);
+            }
         }
         self
     }
@@ -380,11 +511,13 @@ impl<'a, 'b> GpuProgramBinding<'a, 'b> {
         location: &UniformLocation,
         value: &[Vector2<f32>],
     ) -> &mut Self {
-        unsafe {
-            self.state.gl.uniform_2_f32_slice(
-                Some(&location.id),
-                std::slice::from_raw_parts(value.as_ptr() as *const f32, value.len() * 2),
-            );
+        if self.begin_upload(location, array_as_u8_slice(value)) {
+            unsafe {
+                self.state.gl.uniform_2_f32_slice(
+                    Some(&location.id),
+                    std::slice::from_raw_parts(value.as_ptr() as *const f32, value.len() * 2),
+                );
+            }
         }
         self
     }
@@ -395,11 +528,13 @@ impl<'a, 'b> GpuProgramBinding<'a, 'b> {
         location: &UniformLocation,
         value: &[Vector3<f32>],
     ) -> &mut Self {
-        unsafe {
-            self.state.gl.uniform_3_f32_slice(
-                Some(&location.id),
-                std::slice::from_raw_parts(value.as_ptr() as *const f32, value.len() * 3),
-            );
+        if self.begin_upload(location, array_as_u8_slice(value)) {
+            unsafe {
+                self.state.gl.uniform_3_f32_slice(
+                    Some(&location.id),
+                    std::slice::from_raw_parts(value.as_ptr() as *const f32, value.len() * 3),
+                );
+            }
         }
         self
     }
@@ -410,21 +545,25 @@ impl<'a, 'b> GpuProgramBinding<'a, 'b> {
         location: &UniformLocation,
         value: &[Vector4<f32>],
     ) -> &mut Self {
-        unsafe {
-            self.state.gl.uniform_4_f32_slice(
-                Some(&location.id),
-                std::slice::from_raw_parts(value.as_ptr() as *const f32, value.len() * 4),
-            );
+        if self.begin_upload(location, array_as_u8_slice(value)) {
+            unsafe {
+                self.state.gl.uniform_4_f32_slice(
+                    Some(&location.id),
+                    std::slice::from_raw_parts(value.as_ptr() as *const f32, value.len() * 4),
+                );
+            }
         }
         self
     }
 
     #[inline(always)]
     pub fn set_matrix2(&mut self, location: &UniformLocation, value: &Matrix2<f32>) -> &mut Self {
-        unsafe {
-            self.state
-                .gl
-                .uniform_matrix_2_f32_slice(Some(&location.id), false, value.as_slice());
+        if self.begin_upload(location, array_as_u8_slice(value.as_slice())) {
+            unsafe {
+                self.state
+                    .gl
+                    .uniform_matrix_2_f32_slice(Some(&location.id), false, value.as_slice());
+            }
         }
         self
     }
@@ -435,22 +574,26 @@ impl<'a, 'b> GpuProgramBinding<'a, 'b> {
         location: &UniformLocation,
         value: &[Matrix2<f32>],
     ) -> &mut Self {
-        unsafe {
-            self.state.gl.uniform_matrix_2_f32_slice(
-                Some(&location.id),
-                false,
-                std::slice::from_raw_parts(value.as_ptr() as *const f32, value.len() * 4),
-            );
+        if self.begin_upload(location, array_as_u8_slice(value)) {
+            unsafe {
+                self.state.gl.uniform_matrix_2_f32_slice(
+                    Some(&location.id),
+                    false,
+                    std::slice::from_raw_parts(value.as_ptr() as *const f32, value.len() * 4),
+                );
+            }
         }
         self
     }
 
     #[inline(always)]
     pub fn set_matrix3(&mut self, location: &UniformLocation, value: &Matrix3<f32>) -> &mut Self {
-        unsafe {
-            self.state
-                .gl
-                .uniform_matrix_3_f32_slice(Some(&location.id), false, value.as_slice());
+        if self.begin_upload(location, array_as_u8_slice(value.as_slice())) {
+            unsafe {
+                self.state
+                    .gl
+                    .uniform_matrix_3_f32_slice(Some(&location.id), false, value.as_slice());
+            }
         }
         self
     }
@@ -461,22 +604,26 @@ impl<'a, 'b> GpuProgramBinding<'a, 'b> {
         location: &UniformLocation,
         value: &[Matrix3<f32>],
     ) -> &mut Self {
-        unsafe {
-            self.state.gl.uniform_matrix_3_f32_slice(
-                Some(&location.id),
-                false,
-                std::slice::from_raw_parts(value.as_ptr() as *const f32, value.len() * 9),
-            );
+        if self.begin_upload(location, array_as_u8_slice(value)) {
+            unsafe {
+                self.state.gl.uniform_matrix_3_f32_slice(
+                    Some(&location.id),
+                    false,
+                    std::slice::from_raw_parts(value.as_ptr() as *const f32, value.len() * 9),
+                );
+            }
         }
         self
     }
 
     #[inline(always)]
     pub fn set_matrix4(&mut self, location: &UniformLocation, value: &Matrix4<f32>) -> &mut Self {
-        unsafe {
-            self.state
-                .gl
-                .uniform_matrix_4_f32_slice(Some(&location.id), false, value.as_slice());
+        if self.begin_upload(location, array_as_u8_slice(value.as_slice())) {
+            unsafe {
+                self.state
+                    .gl
+                    .uniform_matrix_4_f32_slice(Some(&location.id), false, value.as_slice());
+            }
         }
         self
     }
@@ -487,35 +634,103 @@ impl<'a, 'b> GpuProgramBinding<'a, 'b> {
         location: &UniformLocation,
         value: &[Matrix4<f32>],
     ) -> &mut Self {
-        unsafe {
-            self.state.gl.uniform_matrix_4_f32_slice(
-                Some(&location.id),
-                false,
-                std::slice::from_raw_parts(value.as_ptr() as *const f32, value.len() * 16),
-            );
+        if self.begin_upload(location, array_as_u8_slice(value)) {
+            unsafe {
+                self.state.gl.uniform_matrix_4_f32_slice(
+                    Some(&location.id),
+                    false,
+                    std::slice::from_raw_parts(value.as_ptr() as *const f32, value.len() * 16),
+                );
+            }
         }
         self
     }
 
     #[inline(always)]
     pub fn set_linear_color(&mut self, location: &UniformLocation, value: &Color) -> &mut Self {
-        unsafe {
-            let srgb_a = value.srgb_to_linear_f32();
-            self.state
-                .gl
-                .uniform_4_f32(Some(&location.id), srgb_a.x, srgb_a.y, srgb_a.z, srgb_a.w);
+        let srgb_a = value.srgb_to_linear_f32();
+        if self.begin_upload(location, value_as_u8_slice(&srgb_a)) {
+            unsafe {
+                self.state
+                    .gl
+                    .uniform_4_f32(Some(&location.id), srgb_a.x, srgb_a.y, srgb_a.z, srgb_a.w);
+            }
         }
         self
     }
 
     #[inline(always)]
     pub fn set_srgb_color(&mut self, location: &UniformLocation, value: &Color) -> &mut Self {
-        unsafe {
-            let rgba = value.as_frgba();
-            self.state
-                .gl
-                .uniform_4_f32(Some(&location.id), rgba.x, rgba.y, rgba.z, rgba.w);
+        let rgba = value.as_frgba();
+        if self.begin_upload(location, value_as_u8_slice(&rgba)) {
+            unsafe {
+                self.state
+                    .gl
+                    .uniform_4_f32(Some(&location.id), rgba.x, rgba.y, rgba.z, rgba.w);
+            }
         }
         self
     }
 }
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        gpu_program::{prepare_source_code, UniformValueCache},
+        pipeline_state::PipelineStatistics,
+        shader::ShaderTarget,
+    };
+
+    #[test]
+    fn test_uniform_upload_accounting() {
+        let mut cache = UniformValueCache::default();
+        let mut statistics = PipelineStatistics::default();
+
+        assert!(cache.begin_upload(&mut statistics, 0, &[1, 2, 3, 4]));
+        assert!(!cache.begin_upload(&mut statistics, 0, &[1, 2, 3, 4]));
+        assert!(cache.begin_upload(&mut statistics, 0, &[1, 2, 3, 5]));
+        // Slots are independent, the same value in another slot is uploaded.
+        assert!(cache.begin_upload(&mut statistics, 3, &[1, 2, 3, 5]));
+        assert!(!cache.begin_upload(&mut statistics, 3, &[1, 2, 3, 5]));
+
+        assert_eq!(statistics.uniform_uploads, 3);
+        assert_eq!(statistics.uniform_uploads_skipped, 2);
+        assert_eq!(statistics.bytes_uploaded, 12);
+    }
+
+    #[test]
+    fn test_uniform_cache_large_values() {
+        let mut cache = UniformValueCache::default();
+        let mut value = vec![7u8; 256];
+
+        assert!(cache.update(0, &value));
+        assert!(!cache.update(0, &value));
+
+        // A change of a single byte must never be skipped.
+        value[200] = 8;
+        assert!(cache.update(0, &value));
+        assert!(!cache.update(0, &value));
+
+        // Switching between inline and heap storage keeps comparison exact.
+        assert!(cache.update(0, &[1, 2]));
+        assert!(!cache.update(0, &[1, 2]));
+        assert!(cache.update(0, &[1, 2, 0]));
+    }
+
+    #[test]
+    fn test_prepare_source_code_header() {
+        // The header is chosen by the target only, WebGL gets no special treatment at
+        // compile time.
+        for target in [
+            ShaderTarget::Gl33Core,
+            ShaderTarget::Gl4 { minor: 6 },
+            ShaderTarget::Gles30,
+            ShaderTarget::WebGl2,
+        ] {
+            let source = prepare_source_code("void main() {}", target);
+            assert!(source.starts_with(&target.header()), "{:?}", target);
+            assert_eq!(source.matches("#version").count(), 1, "{:?}", target);
+            assert!(source.ends_with("void main() {}"));
+        }
+    }
+}
@@ -0,0 +1,521 @@
+//! GPU time measurement based on timer queries.
+//!
+//! GPU executes commands asynchronously, so results of a frame become available a few frames
+//! later. [`GpuTimer`] keeps queries of several frames in flight and collects results when
+//! they are ready, so reading them never stalls the pipeline (unless too many frames are
+//! pending).
+//!
+//! `TIMESTAMP` queries are used when available. Otherwise `TIME_ELAPSED` queries are used:
+//! they cannot be nested, so the frame is split into segments at every scope boundary and
+//! times of the boundaries are restored by summing durations of the segments. Time between
+//! frames is not measured in this mode. Timestamp queries are read through raw native
+//! functions, so WebGL always uses `TIME_ELAPSED`.
+
+use crate::capabilities::GlCapabilities;
+use crate::native_buffer::FrameworkError;
+#[cfg(not(target_arch = "wasm32"))]
+use crate::pipeline_state::query_name;
+use crate::pipeline_state::PipelineState;
+use crate::{Log, MessageKind};
+use glow::HasContext;
+use std::collections::VecDeque;
+use std::fmt::Write;
+use std::ops::{Deref, DerefMut};
+
+// Amount of frames which results can be pending, GPU is rarely more than 3 frames behind.
+// If there are more, the oldest frame is waited for.
+const MAX_PENDING_FRAMES: usize = 4;
+
+const DEFAULT_HISTORY_LEN: usize = 60;
+
+const FRAME_SCOPE_NAME: &str = "Frame";
+
+// `GL_GPU_DISJOINT_EXT` of `GL_EXT_disjoint_timer_query`.
+const GPU_DISJOINT: u32 = 0x8FBB;
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+enum TimerMode {
+    // Every scope boundary is a timestamp query.
+    #[cfg(not(target_arch = "wasm32"))]
+    Timestamp,
+    // Every scope boundary begins a time elapsed query of the next segment.
+    Elapsed,
+}
+
+/// Measured time of a named scope.
+#[derive(Clone, Debug)]
+pub struct GpuScopeTiming {
+    pub name: String,
+    /// Nesting level of the scope, 0 is the frame itself.
+    pub depth: usize,
+    /// GPU timestamp of the beginning of the scope in nanoseconds.
+    pub start_ns: u64,
+    pub duration_ns: u64,
+}
+
+/// Timings of all scopes of a frame, in order of their beginning.
+#[derive(Clone, Debug)]
+pub struct GpuFrameTiming {
+    /// Index of the frame, counted from creation of the timer.
+    pub frame: u64,
+    pub scopes: Vec<GpuScopeTiming>,
+}
+
+impl GpuFrameTiming {
+    /// GPU time of the whole frame in nanoseconds.
+    pub fn duration_ns(&self) -> u64 {
+        self.scopes.first().map_or(0, |scope| scope.duration_ns)
+    }
+}
+
+// Beginning and end of a scope are indices of boundary markers of the frame.
+struct PendingScope {
+    name: String,
+    depth: usize,
+    begin: usize,
+    end: Option<usize>,
+}
+
+struct PendingFrame {
+    frame: u64,
+    scopes: Vec<PendingScope>,
+    // Query of every scope boundary, in order of writing.
+    markers: Vec<glow::Query>,
+}
+
+/// Measures GPU time of the frame and of named nested scopes inside of it.
+///
+/// ```ignore
+/// timer.begin_frame(state);
+/// {
+///     let mut scope = timer.scope(state, "Shadows");
+///     // Guard dereferences to the pipeline state.
+///     geometry.bind(&mut scope).draw();
+/// }
+/// timer.end_frame(state);
+/// println!("{}", timer.to_chrome_trace());
+/// ```
+pub struct GpuTimer {
+    state: *mut PipelineState,
+    mode: TimerMode,
+    // A time elapsed query is running, used in `TimerMode::Elapsed` only.
+    elapsed_active: bool,
+    // Sum of measured segments of collected frames, used in `TimerMode::Elapsed` only.
+    elapsed_clock: u64,
+    free_queries: Vec<glow::Query>,
+    current: Option<PendingFrame>,
+    // Indices of open scopes in the current frame.
+    open_scopes: Vec<usize>,
+    pending: VecDeque<PendingFrame>,
+    history: VecDeque<GpuFrameTiming>,
+    history_len: usize,
+    frame_counter: u64,
+}
+
+impl GpuTimer {
+    pub fn new(state: &mut PipelineState) -> Result<Self, FrameworkError> {
+        let mode = timer_mode(state.capabilities()).ok_or_else(|| {
+            FrameworkError::UnsupportedFeature("Timer query".to_owned())
+        })?;
+
+        Ok(Self {
+            state,
+            mode,
+            elapsed_active: false,
+            elapsed_clock: 0,
+            free_queries: Default::default(),
+            current: None,
+            open_scopes: Default::default(),
+            pending: Default::default(),
+            history: Default::default(),
+            history_len: DEFAULT_HISTORY_LEN,
+            frame_counter: 0,
+        })
+    }
+
+    /// Starts a new frame, it is measured as a root scope. Collects results of previous
+    /// frames that are ready.
+    pub fn begin_frame(&mut self, state: &mut PipelineState) -> Result<(), FrameworkError> {
+        if self.current.is_some() {
+            self.end_frame(state)?;
+        }
+
+        self.collect(state, false);
+
+        self.current = Some(PendingFrame {
+            frame: self.frame_counter,
+            scopes: Default::default(),
+            markers: Default::default(),
+        });
+        self.frame_counter += 1;
+
+        self.begin_scope(state, FRAME_SCOPE_NAME)
+    }
+
+    /// Finishes the frame, all scopes that are still open are closed.
+    pub fn end_frame(&mut self, state: &mut PipelineState) -> Result<(), FrameworkError> {
+        while !self.open_scopes.is_empty() {
+            self.end_scope(state)?;
+        }
+
+        if self.elapsed_active {
+            unsafe { state.gl.end_query(glow::TIME_ELAPSED) };
+            self.elapsed_active = false;
+        }
+
+        if let Some(frame) = self.current.take() {
+            self.pending.push_back(frame);
+        }
+
+        // Too many frames in flight, wait for the oldest one to keep the pool bounded.
+        self.collect(state, self.pending.len() > MAX_PENDING_FRAMES);
+
+        Ok(())
+    }
+
+    /// Begins a named scope inside of current frame, scopes can be nested. Does nothing if
+    /// there is no current frame.
+    pub fn begin_scope(
+        &mut self,
+        state: &mut PipelineState,
+        name: &str,
+    ) -> Result<(), FrameworkError> {
+        self.open_scope(state, name).map(|_| ())
+    }
+
+    // Returns depth of the opened scope, or `None` if there is no current frame.
+    fn open_scope(
+        &mut self,
+        state: &mut PipelineState,
+        name: &str,
+    ) -> Result<Option<usize>, FrameworkError> {
+        if self.current.is_none() {
+            return Ok(None);
+        }
+
+        let begin = self.write_marker(state)?;
+
+        let depth = self.open_scopes.len();
+        let frame = self.current.as_mut().unwrap();
+        self.open_scopes.push(frame.scopes.len());
+        frame.scopes.push(PendingScope {
+            name: name.to_owned(),
+            depth,
+            begin,
+            end: None,
+        });
+
+        Ok(Some(depth))
+    }
+
+    /// Ends the innermost open scope.
+    pub fn end_scope(&mut self, state: &mut PipelineState) -> Result<(), FrameworkError> {
+        if let Some(index) = self.open_scopes.pop() {
+            if self.current.is_some() {
+                let end = self.write_marker(state)?;
+                if let Some(frame) = self.current.as_mut() {
+                    frame.scopes[index].end = Some(end);
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Begins a named scope which ends when returned guard is dropped. The guard dereferences
+    /// to the pipeline state and can be used to begin nested scopes.
+    pub fn scope<'a>(
+        &'a mut self,
+        state: &'a mut PipelineState,
+        name: &str,
+    ) -> GpuTimerScope<'a> {
+        let depth = match self.open_scope(state, name) {
+            Ok(depth) => depth,
+            Err(error) => {
+                Log::writeln(
+                    MessageKind::Error,
+                    format!("Unable to begin GPU timer scope {}: {}", name, error),
+                );
+                None
+            }
+        };
+
+        GpuTimerScope {
+            timer: self,
+            state,
+            depth,
+        }
+    }
+
+    /// Timings of last frames which results are ready, oldest first.
+    pub fn history(&self) -> impl Iterator<Item = &GpuFrameTiming> {
+        self.history.iter()
+    }
+
+    /// Timing of the most recent frame which results are ready.
+    pub fn last_frame(&self) -> Option<&GpuFrameTiming> {
+        self.history.back()
+    }
+
+    /// Sets amount of frames kept in the history.
+    pub fn set_history_len(&mut self, len: usize) {
+        self.history_len = len;
+        while self.history.len() > len {
+            self.history.pop_front();
+        }
+    }
+
+    /// Exports the history in Chrome trace event format (JSON), it can be opened in
+    /// `chrome://tracing` or Perfetto. Time is relative to the oldest frame in the history.
+    pub fn to_chrome_trace(&self) -> String {
+        let origin = self
+            .history
+            .front()
+            .and_then(|frame| frame.scopes.first())
+            .map_or(0, |scope| scope.start_ns);
+
+        let mut json = String::from("{\"traceEvents\":[");
+        let mut first = true;
+        for frame in self.history.iter() {
+            for scope in frame.scopes.iter() {
+                if !first {
+                    json.push(',');
+                }
+                first = false;
+
+                // Chrome trace uses microseconds.
+                let _ = write!(
+                    json,
+                    "{{\"name\":\"{}\",\"cat\":\"gpu\",\"ph\":\"X\",\"ts\":{:.3},\"dur\":{:.3},\
+                    \"pid\":0,\"tid\":0,\"args\":{{\"frame\":{}}}}}",
+                    escape_json(&scope.name),
+                    scope.start_ns.saturating_sub(origin) as f64 / 1000.0,
+                    scope.duration_ns as f64 / 1000.0,
+                    frame.frame
+                );
+            }
+        }
+        json.push_str("]}");
+        json
+    }
+
+    // Writes a scope boundary to the current frame and returns its index. Must be called
+    // only when there is a current frame.
+    fn write_marker(&mut self, state: &mut PipelineState) -> Result<usize, FrameworkError> {
+        let query = match self.free_queries.pop() {
+            Some(query) => query,
+            None => unsafe { state.gl.create_query()? },
+        };
+
+        unsafe {
+            match self.mode {
+                #[cfg(not(target_arch = "wasm32"))]
+                TimerMode::Timestamp => {
+                    state.gl.raw.QueryCounter(query_name(query), glow::TIMESTAMP);
+                }
+                TimerMode::Elapsed => {
+                    if self.elapsed_active {
+                        state.gl.end_query(glow::TIME_ELAPSED);
+                    }
+                    state.gl.begin_query(glow::TIME_ELAPSED, query);
+                    self.elapsed_active = true;
+                }
+            }
+        }
+
+        let frame = self.current.as_mut().unwrap();
+        frame.markers.push(query);
+        Ok(frame.markers.len() - 1)
+    }
+
+    // Returns time of every marker of a frame in nanoseconds.
+    unsafe fn marker_times(&mut self, state: &PipelineState, markers: &[glow::Query]) -> Vec<u64> {
+        match self.mode {
+            #[cfg(not(target_arch = "wasm32"))]
+            TimerMode::Timestamp => markers
+                .iter()
+                .map(|&query| read_timestamp(state, query))
+                .collect(),
+            TimerMode::Elapsed => {
+                // Marker `i` begins segment `i`, the last segment follows the end of the frame.
+                let mut times = Vec::with_capacity(markers.len());
+                let mut clock = self.elapsed_clock;
+                for &query in markers {
+                    times.push(clock);
+                    clock += state.gl.get_query_parameter_u32(query, glow::QUERY_RESULT) as u64;
+                }
+                self.elapsed_clock = times.last().copied().unwrap_or(clock);
+                times
+            }
+        }
+    }
+
+    // Moves frames which results are available into the history, if `wait` is set the oldest
+    // frame is read even if GPU has not finished it yet.
+    fn collect(&mut self, state: &mut PipelineState, mut wait: bool) {
+        // Reading the disjoint flag clears it, so it is read once and applies to every frame
+        // collected by this call.
+        let mut disjoint = None;
+
+        while let Some(frame) = self.pending.front() {
+            // Queries are finished in order, so the last one tells about the whole frame.
+            let last = frame.markers.last().copied();
+
+            if let Some(last) = last {
+                let available = unsafe {
+                    state
+                        .gl
+                        .get_query_parameter_u32(last, glow::QUERY_RESULT_AVAILABLE)
+                        != 0
+                };
+                if !available && !wait {
+                    break;
+                }
+            }
+            wait = false;
+
+            let frame = self.pending.pop_front().unwrap();
+
+            // Results of disjoint timer queries are undefined if GPU was interrupted (for
+            // example, by power management) while they were running.
+            let disjoint = *disjoint.get_or_insert_with(|| {
+                state.capabilities().is_gles()
+                    && unsafe { state.gl.get_parameter_i32(GPU_DISJOINT) } != 0
+            });
+
+            let times = unsafe { self.marker_times(state, &frame.markers) };
+            self.free_queries.extend(frame.markers.iter().copied());
+
+            if disjoint {
+                continue;
+            }
+
+            let mut scopes = Vec::with_capacity(frame.scopes.len());
+            for scope in frame.scopes {
+                let start_ns = times[scope.begin];
+                let end_ns = scope.end.map_or(start_ns, |end| times[end]);
+
+                scopes.push(GpuScopeTiming {
+                    name: scope.name,
+                    depth: scope.depth,
+                    start_ns,
+                    duration_ns: end_ns.saturating_sub(start_ns),
+                });
+            }
+
+            if self.history_len > 0 {
+                while self.history.len() >= self.history_len {
+                    self.history.pop_front();
+                }
+                self.history.push_back(GpuFrameTiming {
+                    frame: frame.frame,
+                    scopes,
+                });
+            }
+        }
+    }
+}
+
+impl Drop for GpuTimer {
+    fn drop(&mut self) {
+        let mut queries = std::mem::take(&mut self.free_queries);
+        for frame in self.current.take().into_iter().chain(self.pending.drain(..)) {
+            queries.extend(frame.markers);
+        }
+
+        unsafe {
+            let state = &mut *self.state;
+            if self.elapsed_active {
+                state.gl.end_query(glow::TIME_ELAPSED);
+            }
+            for query in queries {
+                state.gl.delete_query(query);
+            }
+        }
+    }
+}
+
+/// A scope of [`GpuTimer`] that ends when dropped.
+pub struct GpuTimerScope<'a> {
+    timer: &'a mut GpuTimer,
+    state: &'a mut PipelineState,
+    // Depth of the scope opened by the guard, `None` if it failed to open one.
+    depth: Option<usize>,
+}
+
+impl<'a> GpuTimerScope<'a> {
+    /// Begins a nested scope.
+    pub fn scope(&mut self, name: &str) -> GpuTimerScope<'_> {
+        self.timer.scope(self.state, name)
+    }
+}
+
+impl Deref for GpuTimerScope<'_> {
+    type Target = PipelineState;
+
+    fn deref(&self) -> &Self::Target {
+        self.state
+    }
+}
+
+impl DerefMut for GpuTimerScope<'_> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        self.state
+    }
+}
+
+impl Drop for GpuTimerScope<'_> {
+    fn drop(&mut self) {
+        // Ends the own scope only, along with nested scopes left open. Nothing is ended if the
+        // scope was not opened or was already closed by the end of the frame.
+        if let Some(depth) = self.depth {
+            while self.timer.open_scopes.len() > depth {
+                if let Err(error) = self.timer.end_scope(self.state) {
+                    Log::writeln(
+                        MessageKind::Error,
+                        format!("Unable to end GPU timer scope: {}", error),
+                    );
+                    break;
+                }
+            }
+        }
+    }
+}
+
+fn timer_mode(capabilities: &GlCapabilities) -> Option<TimerMode> {
+    #[cfg(not(target_arch = "wasm32"))]
+    if capabilities.supports_timer_queries() {
+        return Some(TimerMode::Timestamp);
+    }
+
+    if capabilities.supports_time_elapsed_queries() {
+        Some(TimerMode::Elapsed)
+    } else {
+        None
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+unsafe fn read_timestamp(state: &PipelineState, query: glow::Query) -> u64 {
+    let mut value = 0;
+    state
+        .gl
+        .raw
+        .GetQueryObjectui64v(query_name(query), glow::QUERY_RESULT, &mut value);
+    value
+}
+
+fn escape_json(string: &str) -> String {
+    let mut escaped = String::with_capacity(string.len());
+    for c in string.chars() {
+        match c {
+            '"' => escaped.push_str("\\\""),
+            '\\' => escaped.push_str("\\\\"),
+            c if (c as u32) < 0x20 => {
+                let _ = write!(escaped, "\\u{:04x}", c as u32);
+            }
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
@@ -0,0 +1,111 @@
+use crate::native_buffer::FrameworkError;
+use crate::pipeline_state::PipelineState;
+use fyrox::utils::array_as_u8_slice;
+use glow::HasContext;
+use std::marker::PhantomData;
+
+/// Parameters of a single indexed draw call, layout matches the one expected by
+/// `glDrawElementsIndirect`.
+#[repr(C)]
+#[derive(Copy, Clone, PartialEq, Eq, Debug, Default)]
+pub struct DrawElementsIndirectCommand {
+    /// Amount of indices to draw.
+    pub count: u32,
+    pub instance_count: u32,
+    /// Offset of the first index in the element buffer, in indices.
+    pub first_index: u32,
+    /// A value added to every index before fetching vertices.
+    pub base_vertex: i32,
+    /// First instance, affects instanced attributes only. Must be 0 if the context does
+    /// not support base instance (GL 4.2).
+    pub base_instance: u32,
+}
+
+/// A list of draw commands for [`crate::geometry_buffer::GeometryBufferBinding::draw_indirect`]
+/// and [`crate::geometry_buffer::GeometryBufferBinding::multi_draw_indirect`]. Commands are
+/// stored in a GPU buffer if the context supports indirect rendering, and always on CPU side
+/// for validation and for fallback path.
+pub struct IndirectBuffer {
+    state: *mut PipelineState,
+    id: Option<glow::Buffer>,
+    commands: Vec<DrawElementsIndirectCommand>,
+    // Force compiler to not implement Send and Sync, because OpenGL is not thread-safe.
+    thread_mark: PhantomData<*const u8>,
+}
+
+impl IndirectBuffer {
+    pub fn new(
+        state: &mut PipelineState,
+        commands: &[DrawElementsIndirectCommand],
+    ) -> Result<Self, FrameworkError> {
+        let id = if state.capabilities().supports_draw_indirect() {
+            Some(unsafe { state.gl.create_buffer()? })
+        } else {
+            None
+        };
+
+        let mut buffer = Self {
+            state,
+            id,
+            commands: Default::default(),
+            thread_mark: Default::default(),
+        };
+        buffer.set_commands(state, commands);
+
+        Ok(buffer)
+    }
+
+    /// Replaces all commands of the buffer.
+    pub fn set_commands(
+        &mut self,
+        state: &mut PipelineState,
+        commands: &[DrawElementsIndirectCommand],
+    ) {
+        self.commands.clear();
+        self.commands.extend_from_slice(commands);
+
+        if self.id.is_some() {
+            let data = array_as_u8_slice(commands);
+            state.frame_statistics.get_mut().bytes_uploaded += data.len();
+
+            unsafe {
+                self.bind(state);
+                state
+                    .gl
+                    .buffer_data_u8_slice(glow::DRAW_INDIRECT_BUFFER, data, glow::DYNAMIC_DRAW);
+            }
+        }
+    }
+
+    pub fn commands(&self) -> &[DrawElementsIndirectCommand] {
+        &self.commands
+    }
+
+    pub fn len(&self) -> usize {
+        self.commands.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.commands.is_empty()
+    }
+
+    /// Returns true if commands are stored on GPU.
+    pub fn is_gpu_resident(&self) -> bool {
+        self.id.is_some()
+    }
+
+    // Indirect buffer binding is not tracked by pipeline state, so it is bound before use.
+    pub(crate) unsafe fn bind(&self, state: &PipelineState) {
+        state.gl.bind_buffer(glow::DRAW_INDIRECT_BUFFER, self.id);
+    }
+}
+
+impl Drop for IndirectBuffer {
+    fn drop(&mut self) {
+        if let Some(id) = self.id {
+            unsafe {
+                (*self.state).gl.delete_buffer(id);
+            }
+        }
+    }
+}
@@ -1,3 +1,5 @@
+extern crate self as rs_gl;
+
 mod gl;
 pub mod types;
 pub mod mesh;
@@ -5,12 +7,18 @@ pub mod vector;
 pub mod vertex_buffer;
 pub mod native_buffer;
 pub mod geometry_buffer;
+pub mod indirect_buffer;
 pub mod pipeline_state;
+pub mod capabilities;
 pub mod surface_data;
+pub mod obj;
 pub mod vertex;
 pub mod shader;
+pub mod shader_validation;
 pub mod gpu_program;
 pub mod gpu_texture;
+pub mod gpu_timer;
+pub mod occlusion_query;
 pub mod gl_wrapper;
 mod shader_program;
 
@@ -24,6 +32,7 @@ pub use fyrox_core as core;
 pub use fyrox_core::sstorage::ImmutableString;
 
 pub use pipeline_state::PipelineState;
+pub use capabilities::GlCapabilities;
 
 pub use std::marker::PhantomData;
 pub use fxhash::FxHashMap;
@@ -1,5 +1,5 @@
 use crate::{
-vertex_buffer::{VertexBuffer,VertexAttributeDataType},
+vertex_buffer::{VertexBuffer,VertexAttributeDataType,ValidationError},
 pipeline_state::{PipelineState},
 };
 
@@ -22,18 +22,17 @@ pub struct NativeBuffer {
     thread_mark: PhantomData<*const u8>,
 }
 
-pub struct NativeBufferBuilder {
+pub struct NativeBufferBuilder<'a> {
     element_size: usize,
     kind: GeometryBufferKind,
     attributes: Vec<AttributeDefinition>,
-    data: *const u8,
-    data_size: usize,
+    data: &'a [u8],
 }
 
 
 
-impl NativeBufferBuilder{
-    pub fn from_vertex_buffer(buffer: &VertexBuffer, kind: GeometryBufferKind) -> Self {
+impl<'a> NativeBufferBuilder<'a> {
+    pub fn from_vertex_buffer(buffer: &'a VertexBuffer, kind: GeometryBufferKind) -> Self {
         Self {
             element_size: buffer.vertex_size_in_byte() as usize,
             kind,
@@ -59,30 +58,70 @@ impl NativeBufferBuilder{
                         (VertexAttributeDataType::U8, 2) => AttributeKind::UnsignedByte2,
                         (VertexAttributeDataType::U8, 3) => AttributeKind::UnsignedByte3,
                         (VertexAttributeDataType::U8, 4) => AttributeKind::UnsignedByte4,
+                        (VertexAttributeDataType::I32, 1) => AttributeKind::Int,
+                        (VertexAttributeDataType::I32, 2) => AttributeKind::Int2,
+                        (VertexAttributeDataType::I32, 3) => AttributeKind::Int3,
+                        (VertexAttributeDataType::I32, 4) => AttributeKind::Int4,
+                        (VertexAttributeDataType::I16, 1) => AttributeKind::Short,
+                        (VertexAttributeDataType::I16, 2) => AttributeKind::Short2,
+                        (VertexAttributeDataType::I16, 3) => AttributeKind::Short3,
+                        (VertexAttributeDataType::I16, 4) => AttributeKind::Short4,
+                        (VertexAttributeDataType::I8, 1) => AttributeKind::Byte,
+                        (VertexAttributeDataType::I8, 2) => AttributeKind::Byte2,
+                        (VertexAttributeDataType::I8, 3) => AttributeKind::Byte3,
+                        (VertexAttributeDataType::I8, 4) => AttributeKind::Byte4,
+                        (VertexAttributeDataType::F16, 1) => AttributeKind::HalfFloat,
+                        (VertexAttributeDataType::F16, 2) => AttributeKind::HalfFloat2,
+                        (VertexAttributeDataType::F16, 3) => AttributeKind::HalfFloat3,
+                        (VertexAttributeDataType::F16, 4) => AttributeKind::HalfFloat4,
+                        (VertexAttributeDataType::PackedI2_10_10_10Rev, _) => {
+                            AttributeKind::PackedInt2_10_10_10Rev
+                        }
+                        (VertexAttributeDataType::PackedU2_10_10_10Rev, _) => {
+                            AttributeKind::PackedUnsignedInt2_10_10_10Rev
+                        }
                         _ => unreachable!(),
                     },
-                    normalized: false,
-                    divisor: 0,
+                    normalized: a.normalized,
+                    integer: a.integer,
+                    divisor: a.divisor as u32,
                 })
                 .collect(),
-            data: buffer.raw_data().as_ptr(),
-            data_size: buffer.raw_data().len(),
+            data: buffer.raw_data(),
         }
     }
 
 
+    /// Creates one builder per stream of a deinterleaved vertex buffer (see
+    /// `VertexBuffer::deinterleave`). The builders borrow data of the streams.
+    pub fn from_vertex_streams(streams: &'a [VertexBuffer], kind: GeometryBufferKind) -> Vec<Self> {
+        streams
+            .iter()
+            .map(|stream| Self::from_vertex_buffer(stream, kind))
+            .collect()
+    }
+
     pub fn build(self, state: &mut PipelineState) -> Result<NativeBuffer, FrameworkError> {
+        let max_stride = state.capabilities().max_vertex_attrib_stride as usize;
+        if self.element_size > max_stride {
+            return Err(FrameworkError::InvalidVertexLayout(
+                ValidationError::VertexSizeTooLarge {
+                    size: self.element_size,
+                    max: max_stride,
+                },
+            ));
+        }
+
         let vbo = unsafe { state.gl.create_buffer()? };
 
         state.set_vertex_buffer_object(Some(vbo));
 
-        if self.data_size > 0 {
+        if !self.data.is_empty() {
+            state.frame_statistics.get_mut().bytes_uploaded += self.data.len();
             unsafe {
-                state.gl.buffer_data_u8_slice(
-                    glow::ARRAY_BUFFER,
-                    std::slice::from_raw_parts(self.data, self.data_size),
-                    self.kind as u32,
-                );
+                state
+                    .gl
+                    .buffer_data_u8_slice(glow::ARRAY_BUFFER, self.data, self.kind as u32);
             }
         }
 
@@ -91,21 +130,37 @@ impl NativeBufferBuilder{
             id: vbo,
             kind: self.kind,
             element_size: self.element_size,
-            size_bytes: self.data_size,
+            size_bytes: self.data.len(),
             thread_mark: Default::default(),
         };
 
         let mut offset = 0usize;
         for definition in self.attributes {
             unsafe {
-                state.gl.vertex_attrib_pointer_f32(
-                    definition.location,
-                    definition.kind.length() as i32,
-                    definition.kind.get_type(),
-                    definition.normalized,
-                    self.element_size as i32,
-                    offset as i32,
-                );
+                if definition.integer {
+                    if !definition.kind.is_integer() {
+                        state.set_vertex_buffer_object(Default::default());
+                        state.gl.delete_buffer(vbo);
+                        return Err(FrameworkError::InvalidAttributeDescriptor);
+                    }
+
+                    state.gl.vertex_attrib_pointer_i32(
+                        definition.location,
+                        definition.kind.length() as i32,
+                        definition.kind.get_type(),
+                        self.element_size as i32,
+                        offset as i32,
+                    );
+                } else {
+                    state.gl.vertex_attrib_pointer_f32(
+                        definition.location,
+                        definition.kind.length() as i32,
+                        definition.kind.get_type(),
+                        definition.normalized,
+                        self.element_size as i32,
+                        offset as i32,
+                    );
+                }
                 state
                     .gl
                     .vertex_attrib_divisor(definition.location, definition.divisor);
@@ -115,6 +170,7 @@ impl NativeBufferBuilder{
 
                 if offset > self.element_size {
                     state.set_vertex_buffer_object(Default::default());
+                    state.gl.delete_buffer(vbo);
                     return Err(FrameworkError::InvalidAttributeDescriptor);
                 }
             }
@@ -128,6 +184,8 @@ pub struct AttributeDefinition {
     pub location: u32,
     pub kind: AttributeKind,
     pub normalized: bool,
+    /// Attribute is read as integer in shader, `normalized` is ignored in this case.
+    pub integer: bool,
     pub divisor: u32,
 }
 
@@ -153,6 +211,31 @@ pub enum AttributeKind {
     UnsignedInt2,
     UnsignedInt3,
     UnsignedInt4,
+
+    Byte,
+    Byte2,
+    Byte3,
+    Byte4,
+
+    Short,
+    Short2,
+    Short3,
+    Short4,
+
+    Int,
+    Int2,
+    Int3,
+    Int4,
+
+    HalfFloat,
+    HalfFloat2,
+    HalfFloat3,
+    HalfFloat4,
+
+    /// Four signed components packed in 32 bits (`INT_2_10_10_10_REV`).
+    PackedInt2_10_10_10Rev,
+    /// Four unsigned components packed in 32 bits (`UNSIGNED_INT_2_10_10_10_REV`).
+    PackedUnsignedInt2_10_10_10Rev,
 }
 
 
@@ -178,9 +261,42 @@ impl AttributeKind {
             AttributeKind::UnsignedInt2 => size_of::<u32>() * 2,
             AttributeKind::UnsignedInt3 => size_of::<u32>() * 3,
             AttributeKind::UnsignedInt4 => size_of::<u32>() * 4,
+
+            AttributeKind::Byte => size_of::<i8>(),
+            AttributeKind::Byte2 => size_of::<i8>() * 2,
+            AttributeKind::Byte3 => size_of::<i8>() * 3,
+            AttributeKind::Byte4 => size_of::<i8>() * 4,
+
+            AttributeKind::Short => size_of::<i16>(),
+            AttributeKind::Short2 => size_of::<i16>() * 2,
+            AttributeKind::Short3 => size_of::<i16>() * 3,
+            AttributeKind::Short4 => size_of::<i16>() * 4,
+
+            AttributeKind::Int => size_of::<i32>(),
+            AttributeKind::Int2 => size_of::<i32>() * 2,
+            AttributeKind::Int3 => size_of::<i32>() * 3,
+            AttributeKind::Int4 => size_of::<i32>() * 4,
+
+            AttributeKind::HalfFloat => size_of::<u16>(),
+            AttributeKind::HalfFloat2 => size_of::<u16>() * 2,
+            AttributeKind::HalfFloat3 => size_of::<u16>() * 3,
+            AttributeKind::HalfFloat4 => size_of::<u16>() * 4,
+
+            AttributeKind::PackedInt2_10_10_10Rev
+            | AttributeKind::PackedUnsignedInt2_10_10_10Rev => size_of::<u32>(),
         }
     }
 
+    fn is_integer(self) -> bool {
+        !matches!(
+            self.get_type(),
+            glow::FLOAT
+                | glow::HALF_FLOAT
+                | glow::INT_2_10_10_10_REV
+                | glow::UNSIGNED_INT_2_10_10_10_REV
+        )
+    }
+
     fn get_type(self) -> u32 {
         match self {
             AttributeKind::Float
@@ -202,6 +318,29 @@ impl AttributeKind {
             | AttributeKind::UnsignedInt2
             | AttributeKind::UnsignedInt3
             | AttributeKind::UnsignedInt4 => glow::UNSIGNED_INT,
+
+            AttributeKind::Byte
+            | AttributeKind::Byte2
+            | AttributeKind::Byte3
+            | AttributeKind::Byte4 => glow::BYTE,
+
+            AttributeKind::Short
+            | AttributeKind::Short2
+            | AttributeKind::Short3
+            | AttributeKind::Short4 => glow::SHORT,
+
+            AttributeKind::Int
+            | AttributeKind::Int2
+            | AttributeKind::Int3
+            | AttributeKind::Int4 => glow::INT,
+
+            AttributeKind::HalfFloat
+            | AttributeKind::HalfFloat2
+            | AttributeKind::HalfFloat3
+            | AttributeKind::HalfFloat4 => glow::HALF_FLOAT,
+
+            AttributeKind::PackedInt2_10_10_10Rev => glow::INT_2_10_10_10_REV,
+            AttributeKind::PackedUnsignedInt2_10_10_10Rev => glow::UNSIGNED_INT_2_10_10_10_REV,
         }
     }
 
@@ -210,22 +349,40 @@ impl AttributeKind {
             AttributeKind::Float
             | AttributeKind::UnsignedByte
             | AttributeKind::UnsignedShort
-            | AttributeKind::UnsignedInt => 1,
+            | AttributeKind::UnsignedInt
+            | AttributeKind::Byte
+            | AttributeKind::Short
+            | AttributeKind::Int
+            | AttributeKind::HalfFloat => 1,
 
             AttributeKind::Float2
             | AttributeKind::UnsignedByte2
             | AttributeKind::UnsignedShort2
-            | AttributeKind::UnsignedInt2 => 2,
+            | AttributeKind::UnsignedInt2
+            | AttributeKind::Byte2
+            | AttributeKind::Short2
+            | AttributeKind::Int2
+            | AttributeKind::HalfFloat2 => 2,
 
             AttributeKind::Float3
             | AttributeKind::UnsignedByte3
             | AttributeKind::UnsignedShort3
-            | AttributeKind::UnsignedInt3 => 3,
+            | AttributeKind::UnsignedInt3
+            | AttributeKind::Byte3
+            | AttributeKind::Short3
+            | AttributeKind::Int3
+            | AttributeKind::HalfFloat3 => 3,
 
             AttributeKind::Float4
             | AttributeKind::UnsignedByte4
             | AttributeKind::UnsignedShort4
-            | AttributeKind::UnsignedInt4 => 4,
+            | AttributeKind::UnsignedInt4
+            | AttributeKind::Byte4
+            | AttributeKind::Short4
+            | AttributeKind::Int4
+            | AttributeKind::HalfFloat4
+            | AttributeKind::PackedInt2_10_10_10Rev
+            | AttributeKind::PackedUnsignedInt2_10_10_10Rev => 4,
         }
     }
 }
@@ -316,6 +473,12 @@ pub enum FrameworkError {
     /// OpenGL failed to construct framebuffer.
     #[error("OpenGL failed to construct framebuffer.")]
     FailedToConstructFBO,
+    /// Vertex layout is not supported by the context.
+    #[error("Invalid vertex layout: {0}")]
+    InvalidVertexLayout(ValidationError),
+    /// Means that the context does not support a feature required by an object.
+    #[error("{0} is not supported by the context")]
+    UnsupportedFeature(String),
     /// Custom error. Usually used for internal errors.
     #[error("Custom error: {0}")]
     Custom(String),
@@ -0,0 +1,620 @@
+//! Wavefront OBJ/MTL importer. Polygons are triangulated as fans, so they are expected to
+//! be convex. Faces are split into surfaces by material, faces without material form a
+//! separate surface. Lines, points, curves and smoothing groups are ignored.
+
+use crate::{
+    core::{
+        algebra::{Vector2, Vector3, Vector4},
+        math::TriangleDefinition,
+    },
+    surface_data::{SurfaceData, TriangleBuffer},
+    vertex::StaticVertex,
+    vertex_buffer::{ValidationError, VertexBuffer, VertexFetchError},
+    Log, MessageKind,
+};
+use fxhash::FxHashMap;
+use std::{
+    path::{Path, PathBuf},
+    str::SplitWhitespace,
+};
+
+/// An error that may occur during OBJ or MTL import.
+#[derive(Debug, thiserror::Error)]
+pub enum ObjError {
+    /// IO error.
+    #[error("An i/o error has occurred {0:?}")]
+    Io(std::io::Error),
+    /// Source has invalid syntax or values.
+    #[error("{}:{}: {}", source_name, line, message)]
+    Parse {
+        /// Name of the source file.
+        source_name: String,
+        /// Number of the line, starting from 1.
+        line: usize,
+        /// Description of the error.
+        message: String,
+    },
+    /// Imported vertices do not fit the vertex buffer.
+    #[error("Invalid vertex buffer: {0}")]
+    Validation(ValidationError),
+    /// Tangents could not be calculated.
+    #[error("Failed to calculate tangents: {0}")]
+    Tangents(VertexFetchError),
+}
+
+impl From<std::io::Error> for ObjError {
+    fn from(e: std::io::Error) -> Self {
+        Self::Io(e)
+    }
+}
+
+impl From<ValidationError> for ObjError {
+    fn from(e: ValidationError) -> Self {
+        Self::Validation(e)
+    }
+}
+
+impl From<VertexFetchError> for ObjError {
+    fn from(e: VertexFetchError) -> Self {
+        Self::Tangents(e)
+    }
+}
+
+/// A material defined in an MTL file.
+#[derive(Clone, Debug)]
+pub struct ObjMaterial {
+    /// Name of the material (`newmtl`).
+    pub name: String,
+    /// Ambient color (`Ka`).
+    pub ambient_color: Vector3<f32>,
+    /// Diffuse color (`Kd`).
+    pub diffuse_color: Vector3<f32>,
+    /// Specular color (`Ks`).
+    pub specular_color: Vector3<f32>,
+    /// Specular exponent (`Ns`).
+    pub shininess: f32,
+    /// Opacity (`d`, or `1 - Tr`).
+    pub opacity: f32,
+    /// Diffuse texture (`map_Kd`), as written in the file.
+    pub diffuse_map: Option<String>,
+    /// Normal map (`map_Bump`, `bump` or `norm`), as written in the file.
+    pub normal_map: Option<String>,
+    /// Specular map (`map_Ks`), as written in the file.
+    pub specular_map: Option<String>,
+}
+
+impl ObjMaterial {
+    fn new(name: String) -> Self {
+        Self {
+            name,
+            ambient_color: Vector3::new(0.0, 0.0, 0.0),
+            diffuse_color: Vector3::new(1.0, 1.0, 1.0),
+            specular_color: Vector3::new(0.0, 0.0, 0.0),
+            shininess: 0.0,
+            opacity: 1.0,
+            diffuse_map: None,
+            normal_map: None,
+            specular_map: None,
+        }
+    }
+}
+
+/// A part of an OBJ model that uses a single material.
+#[derive(Debug)]
+pub struct ObjSurface {
+    /// Name of the material (`usemtl`), `None` for faces without material.
+    pub material: Option<String>,
+    /// Geometry of the surface.
+    pub data: SurfaceData,
+}
+
+/// An imported OBJ model.
+#[derive(Debug, Default)]
+pub struct ObjModel {
+    /// Surfaces of the model in order of first use of their materials.
+    pub surfaces: Vec<ObjSurface>,
+    /// Materials of all MTL libraries referenced by the model.
+    pub materials: Vec<ObjMaterial>,
+    /// Names of MTL libraries (`mtllib`), as written in the file.
+    pub material_libraries: Vec<String>,
+}
+
+impl ObjModel {
+    /// Returns a material of given surface, if it was loaded.
+    pub fn material(&self, surface: &ObjSurface) -> Option<&ObjMaterial> {
+        let name = surface.material.as_ref()?;
+        self.materials.iter().find(|m| &m.name == name)
+    }
+}
+
+/// Loads OBJ file and MTL libraries referenced by it, the libraries are looked up relative to
+/// the OBJ file. Missing libraries are reported to the log and skipped.
+pub fn load_obj_file<P: AsRef<Path>>(path: P) -> Result<ObjModel, ObjError> {
+    let path = path.as_ref();
+    let source = std::fs::read_to_string(path)?;
+    let mut model = parse_obj(&source, &path.display().to_string())?;
+
+    let directory = path.parent().map(Path::to_path_buf).unwrap_or_default();
+    for library in model.material_libraries.iter() {
+        let library_path: PathBuf = directory.join(library);
+        match std::fs::read_to_string(&library_path) {
+            Ok(source) => {
+                let materials = parse_mtl(&source, &library_path.display().to_string())?;
+                model.materials.extend(materials);
+            }
+            Err(e) => Log::writeln(
+                MessageKind::Warning,
+                format!(
+                    "Unable to load material library {}: {:?}",
+                    library_path.display(),
+                    e
+                ),
+            ),
+        }
+    }
+
+    Ok(model)
+}
+
+/// Parses OBJ source. `source_name` is used in error messages only. Material libraries are not
+/// loaded, see [`load_obj_file`] and [`parse_mtl`].
+pub fn parse_obj(source: &str, source_name: &str) -> Result<ObjModel, ObjError> {
+    let mut positions = Vec::new();
+    let mut tex_coords = Vec::new();
+    let mut normals = Vec::new();
+    let mut builders: Vec<SurfaceBuilder> = Vec::new();
+    let mut current = None;
+    let mut material_libraries = Vec::new();
+
+    for (n, line) in source.lines().enumerate() {
+        let error = |message: String| ObjError::Parse {
+            source_name: source_name.to_owned(),
+            line: n + 1,
+            message,
+        };
+
+        let line = strip_comment(line);
+        let mut tokens = line.split_whitespace();
+        let keyword = match tokens.next() {
+            Some(keyword) => keyword,
+            None => continue,
+        };
+
+        match keyword {
+            "v" => {
+                let [x, y, z] = parse_floats::<3>(&mut tokens).map_err(error)?;
+                positions.push(Vector3::new(x, y, z));
+            }
+            "vt" => {
+                let u = parse_float(tokens.next()).map_err(error)?;
+                let v = tokens.next().map_or(Ok(0.0), |v| parse_float(Some(v))).map_err(error)?;
+                tex_coords.push(Vector2::new(u, v));
+            }
+            "vn" => {
+                let [x, y, z] = parse_floats::<3>(&mut tokens).map_err(error)?;
+                normals.push(Vector3::new(x, y, z));
+            }
+            "f" => {
+                let corners = tokens
+                    .map(|token| {
+                        parse_corner(token, positions.len(), tex_coords.len(), normals.len())
+                    })
+                    .collect::<Result<Vec<_>, _>>()
+                    .map_err(error)?;
+
+                if corners.len() < 3 {
+                    return Err(error(format!(
+                        "face must have at least 3 vertices, got {}",
+                        corners.len()
+                    )));
+                }
+
+                let index = match current {
+                    Some(index) => index,
+                    None => {
+                        builders.push(SurfaceBuilder::new(None));
+                        current = Some(builders.len() - 1);
+                        builders.len() - 1
+                    }
+                };
+                builders[index].add_polygon(&corners, &positions, &tex_coords, &normals);
+            }
+            "usemtl" => {
+                let name = tokens.collect::<Vec<_>>().join(" ");
+                if name.is_empty() {
+                    return Err(error("material name is missing".to_owned()));
+                }
+                let index = match builders
+                    .iter()
+                    .position(|b| b.material.as_deref() == Some(name.as_str()))
+                {
+                    Some(index) => index,
+                    None => {
+                        builders.push(SurfaceBuilder::new(Some(name)));
+                        builders.len() - 1
+                    }
+                };
+                current = Some(index);
+            }
+            "mtllib" => {
+                // Every token is a separate library.
+                let count = material_libraries.len();
+                material_libraries.extend(tokens.map(|name| name.to_owned()));
+                if material_libraries.len() == count {
+                    return Err(error("material library name is missing".to_owned()));
+                }
+            }
+            // Other elements and grouping statements do not affect triangle geometry.
+            _ => (),
+        }
+    }
+
+    let surfaces = builders
+        .into_iter()
+        .filter(|b| !b.triangles.is_empty())
+        .map(SurfaceBuilder::build)
+        .collect::<Result<Vec<_>, _>>()?;
+
+    Ok(ObjModel {
+        surfaces,
+        materials: Vec::new(),
+        material_libraries,
+    })
+}
+
+/// Parses MTL source. `source_name` is used in error messages only.
+pub fn parse_mtl(source: &str, source_name: &str) -> Result<Vec<ObjMaterial>, ObjError> {
+    let mut materials: Vec<ObjMaterial> = Vec::new();
+
+    for (n, line) in source.lines().enumerate() {
+        let error = |message: String| ObjError::Parse {
+            source_name: source_name.to_owned(),
+            line: n + 1,
+            message,
+        };
+
+        let line = strip_comment(line);
+        let mut tokens = line.split_whitespace();
+        let keyword = match tokens.next() {
+            Some(keyword) => keyword,
+            None => continue,
+        };
+
+        if keyword == "newmtl" {
+            let name = tokens.collect::<Vec<_>>().join(" ");
+            if name.is_empty() {
+                return Err(error("material name is missing".to_owned()));
+            }
+            materials.push(ObjMaterial::new(name));
+            continue;
+        }
+
+        let material = match materials.last_mut() {
+            Some(material) => material,
+            None => return Err(error(format!("`{}` outside of material", keyword))),
+        };
+
+        match keyword {
+            "Ka" => material.ambient_color = parse_color(&mut tokens).map_err(error)?,
+            "Kd" => material.diffuse_color = parse_color(&mut tokens).map_err(error)?,
+            "Ks" => material.specular_color = parse_color(&mut tokens).map_err(error)?,
+            "Ns" => material.shininess = parse_float(tokens.next()).map_err(error)?,
+            "d" => material.opacity = parse_float(tokens.next()).map_err(error)?,
+            "Tr" => material.opacity = 1.0 - parse_float(tokens.next()).map_err(error)?,
+            "map_Kd" => material.diffuse_map = Some(parse_map(tokens).map_err(error)?),
+            "map_Bump" | "map_bump" | "bump" | "norm" => {
+                material.normal_map = Some(parse_map(tokens).map_err(error)?)
+            }
+            "map_Ks" => material.specular_map = Some(parse_map(tokens).map_err(error)?),
+            _ => (),
+        }
+    }
+
+    Ok(materials)
+}
+
+// Indices of position, texture coordinates and normal of a face corner, zero-based.
+type Corner = (usize, Option<usize>, Option<usize>);
+
+struct SurfaceBuilder {
+    material: Option<String>,
+    vertices: Vec<StaticVertex>,
+    // Index of OBJ position of every vertex, used to generate smooth normals.
+    vertex_positions: Vec<usize>,
+    has_normal: Vec<bool>,
+    has_tex_coords: bool,
+    triangles: Vec<TriangleDefinition>,
+    vertex_map: FxHashMap<Corner, u32>,
+}
+
+impl SurfaceBuilder {
+    fn new(material: Option<String>) -> Self {
+        Self {
+            material,
+            vertices: Default::default(),
+            vertex_positions: Default::default(),
+            has_normal: Default::default(),
+            has_tex_coords: false,
+            triangles: Default::default(),
+            vertex_map: Default::default(),
+        }
+    }
+
+    fn add_polygon(
+        &mut self,
+        corners: &[Corner],
+        positions: &[Vector3<f32>],
+        tex_coords: &[Vector2<f32>],
+        normals: &[Vector3<f32>],
+    ) {
+        let indices = corners
+            .iter()
+            .map(|&corner| self.vertex_index(corner, positions, tex_coords, normals))
+            .collect::<Vec<_>>();
+
+        for i in 1..(indices.len() - 1) {
+            self.triangles
+                .push(TriangleDefinition([indices[0], indices[i], indices[i + 1]]));
+        }
+    }
+
+    fn vertex_index(
+        &mut self,
+        corner: Corner,
+        positions: &[Vector3<f32>],
+        tex_coords: &[Vector2<f32>],
+        normals: &[Vector3<f32>],
+    ) -> u32 {
+        if let Some(&index) = self.vertex_map.get(&corner) {
+            return index;
+        }
+
+        let (position, tex_coord, normal) = corner;
+        self.has_tex_coords |= tex_coord.is_some();
+
+        let index = self.vertices.len() as u32;
+        self.vertices.push(StaticVertex {
+            position: positions[position],
+            tex_coord: tex_coord.map(|i| tex_coords[i]).unwrap_or_default(),
+            normal: normal.map(|i| normals[i]).unwrap_or_default(),
+            tangent: Vector4::default(),
+        });
+        self.vertex_positions.push(position);
+        self.has_normal.push(normal.is_some());
+        self.vertex_map.insert(corner, index);
+        index
+    }
+
+    // Generates smooth normals for vertices without normals, faces that share a position
+    // contribute to its normal proportionally to their area.
+    fn generate_normals(&mut self) {
+        if self.has_normal.iter().all(|&has_normal| has_normal) {
+            return;
+        }
+
+        let mut accumulated = FxHashMap::<usize, Vector3<f32>>::default();
+        for triangle in self.triangles.iter() {
+            let [a, b, c] = triangle.0.map(|i| self.vertices[i as usize].position);
+            let face_normal = (b - a).cross(&(c - a));
+            for &i in triangle.0.iter() {
+                *accumulated
+                    .entry(self.vertex_positions[i as usize])
+                    .or_default() += face_normal;
+            }
+        }
+
+        for (i, vertex) in self.vertices.iter_mut().enumerate() {
+            if !self.has_normal[i] {
+                vertex.normal = accumulated
+                    .get(&self.vertex_positions[i])
+                    .and_then(|n| n.try_normalize(f32::EPSILON))
+                    .unwrap_or_else(|| Vector3::new(0.0, 1.0, 0.0));
+            }
+        }
+    }
+
+    fn build(mut self) -> Result<ObjSurface, ObjError> {
+        self.generate_normals();
+
+        let vertex_buffer =
+            VertexBuffer::new(self.vertices.len(), StaticVertex::layout(), self.vertices)?;
+        let mut data = SurfaceData::new(vertex_buffer, TriangleBuffer::new(self.triangles), false);
+
+        // Tangents are defined by texture coordinates, they are meaningless without them.
+        if self.has_tex_coords {
+            data.calculate_tangents()?;
+        }
+
+        Ok(ObjSurface {
+            material: self.material,
+            data,
+        })
+    }
+}
+
+fn strip_comment(line: &str) -> &str {
+    match line.find('#') {
+        Some(position) => &line[..position],
+        None => line,
+    }
+}
+
+fn parse_float(token: Option<&str>) -> Result<f32, String> {
+    let token = token.ok_or_else(|| "unexpected end of line".to_owned())?;
+    token
+        .parse::<f32>()
+        .map_err(|_| format!("`{}` is not a number", token))
+}
+
+fn parse_floats<const N: usize>(tokens: &mut SplitWhitespace) -> Result<[f32; N], String> {
+    let mut values = [0.0; N];
+    for value in values.iter_mut() {
+        *value = parse_float(tokens.next())?;
+    }
+    Ok(values)
+}
+
+fn parse_color(tokens: &mut SplitWhitespace) -> Result<Vector3<f32>, String> {
+    let r = parse_float(tokens.next())?;
+    // A single value means gray color.
+    match tokens.next() {
+        Some(g) => {
+            let g = parse_float(Some(g))?;
+            let b = parse_float(tokens.next())?;
+            Ok(Vector3::new(r, g, b))
+        }
+        None => Ok(Vector3::new(r, r, r)),
+    }
+}
+
+// Texture statements may have options (`-bm 0.5 normal.png`), the file name is the last token.
+fn parse_map(tokens: SplitWhitespace) -> Result<String, String> {
+    tokens
+        .last()
+        .map(str::to_owned)
+        .ok_or_else(|| "texture file name is missing".to_owned())
+}
+
+// Parses `v`, `v/vt`, `v//vn` or `v/vt/vn`, negative indices are relative to the end of
+// the lists.
+fn parse_corner(
+    token: &str,
+    position_count: usize,
+    tex_coord_count: usize,
+    normal_count: usize,
+) -> Result<Corner, String> {
+    let mut parts = token.split('/');
+
+    let position = resolve_index(parts.next(), position_count, "position", token)?
+        .ok_or_else(|| format!("face vertex `{}` has no position", token))?;
+    let tex_coord = resolve_index(parts.next(), tex_coord_count, "texture coordinates", token)?;
+    let normal = resolve_index(parts.next(), normal_count, "normal", token)?;
+
+    if parts.next().is_some() {
+        return Err(format!("invalid face vertex `{}`", token));
+    }
+
+    Ok((position, tex_coord, normal))
+}
+
+fn resolve_index(
+    part: Option<&str>,
+    count: usize,
+    kind: &str,
+    token: &str,
+) -> Result<Option<usize>, String> {
+    let part = match part {
+        Some(part) if !part.is_empty() => part,
+        _ => return Ok(None),
+    };
+
+    let index = part
+        .parse::<i64>()
+        .map_err(|_| format!("invalid {} index in face vertex `{}`", kind, token))?;
+
+    let resolved = if index > 0 {
+        index - 1
+    } else if index < 0 {
+        count as i64 + index
+    } else {
+        -1
+    };
+
+    if resolved < 0 || resolved >= count as i64 {
+        Err(format!(
+            "{} index {} is out of range, there are {} of them",
+            kind, index, count
+        ))
+    } else {
+        Ok(Some(resolved as usize))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        core::algebra::Vector3,
+        obj::{parse_obj, ObjError},
+        vertex_buffer::{VertexAttributeUsage, VertexReadTrait},
+    };
+
+    const QUAD: &str = "v 0 0 0\nv 1 0 0\nv 1 1 0\nv 0 1 0\n";
+
+    fn triangles(source: &str) -> Vec<[u32; 3]> {
+        let model = parse_obj(source, "test.obj").unwrap();
+        model.surfaces[0]
+            .data
+            .geometry_buffer
+            .triangles_ref()
+            .iter()
+            .map(|triangle| triangle.0)
+            .collect()
+    }
+
+    #[test]
+    fn test_obj_quad_fan() {
+        let source = format!("{}f 1 2 3 4\n", QUAD);
+        assert_eq!(triangles(&source), vec![[0, 1, 2], [0, 2, 3]]);
+    }
+
+    #[test]
+    fn test_obj_negative_indices() {
+        let absolute = triangles(&format!("{}f 2 3 4\n", QUAD));
+        let relative = triangles(&format!("{}f -3 -2 -1\n", QUAD));
+        assert_eq!(absolute, relative);
+    }
+
+    #[test]
+    fn test_obj_position_normal_dedup() {
+        let source = format!("{}vn 0 0 1\nf 1//1 2//1 3//1\nf 1//1 3//1 4//1\n", QUAD);
+        let model = parse_obj(&source, "test.obj").unwrap();
+        assert_eq!(model.surfaces.len(), 1);
+
+        let data = &model.surfaces[0].data;
+        assert_eq!(data.vertex_buffer.vertex_count(), 4);
+        assert_eq!(data.geometry_buffer.len(), 2);
+
+        let normal = data
+            .vertex_buffer
+            .get(3)
+            .unwrap()
+            .read_3_f32(VertexAttributeUsage::Normal)
+            .unwrap();
+        assert_eq!(normal, Vector3::new(0.0, 0.0, 1.0));
+    }
+
+    #[test]
+    fn test_obj_usemtl_split() {
+        let source = format!(
+            "mtllib a.mtl b.mtl\n{}f 1 2 3\nusemtl red\nf 1 3 4\nusemtl green\nf 2 3 4\n\
+             usemtl red\nf 1 2 4\n",
+            QUAD
+        );
+        let model = parse_obj(&source, "test.obj").unwrap();
+        assert_eq!(model.material_libraries, vec!["a.mtl", "b.mtl"]);
+
+        let surfaces = model
+            .surfaces
+            .iter()
+            .map(|s| (s.material.as_deref(), s.data.geometry_buffer.len()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            surfaces,
+            vec![(None, 1), (Some("red"), 2), (Some("green"), 1)]
+        );
+    }
+
+    #[test]
+    fn test_obj_parse_error_line() {
+        let source = format!("# comment\n{}\nf 1 2 5\n", QUAD);
+        match parse_obj(&source, "test.obj") {
+            Err(ObjError::Parse {
+                source_name, line, ..
+            }) => {
+                assert_eq!(source_name, "test.obj");
+                assert_eq!(line, 7);
+            }
+            other => panic!("expected parse error, got {:?}", other),
+        }
+    }
+}
@@ -0,0 +1,198 @@
+//! Occlusion queries and conditional rendering.
+//!
+//! Typical usage is to draw a cheap proxy (bounding box) of an object with color and depth
+//! writes disabled inside of a query, and then draw the object itself with
+//! [`OcclusionQuery::conditional_render`], so GPU skips it if the proxy was hidden without
+//! reading the result back on CPU.
+
+use crate::geometry_buffer::{DrawCallStatistics, GeometryBufferBinding};
+use crate::native_buffer::FrameworkError;
+#[cfg(not(target_arch = "wasm32"))]
+use crate::pipeline_state::query_name;
+use crate::pipeline_state::PipelineState;
+use glow::HasContext;
+
+/// What is counted by an occlusion query.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum OcclusionQueryKind {
+    /// Exact amount of samples that passed depth and stencil tests. Not available in GLES,
+    /// `AnySamplesPassed` is used instead.
+    SamplesPassed,
+    /// Whether any sample passed depth and stencil tests, result is 0 or 1.
+    AnySamplesPassed,
+    /// Same as `AnySamplesPassed`, but the implementation may use a faster and less
+    /// precise test that can report visibility of hidden objects. Falls back to
+    /// `AnySamplesPassed` if not supported.
+    AnySamplesPassedConservative,
+}
+
+impl OcclusionQueryKind {
+    fn gl_target(self) -> u32 {
+        match self {
+            OcclusionQueryKind::SamplesPassed => glow::SAMPLES_PASSED,
+            OcclusionQueryKind::AnySamplesPassed => glow::ANY_SAMPLES_PASSED,
+            OcclusionQueryKind::AnySamplesPassedConservative => {
+                glow::ANY_SAMPLES_PASSED_CONSERVATIVE
+            }
+        }
+    }
+}
+
+/// Defines whether GPU waits for the result of the query when it is used for conditional
+/// rendering.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum ConditionalRenderMode {
+    /// Wait for the result.
+    Wait = glow::QUERY_WAIT as isize,
+    /// Render unconditionally if the result is not ready yet.
+    NoWait = glow::QUERY_NO_WAIT as isize,
+    /// Wait for the result, the implementation may discard rendering per screen region.
+    ByRegionWait = glow::QUERY_BY_REGION_WAIT as isize,
+    /// Do not wait for the result, the implementation may discard rendering per screen
+    /// region.
+    ByRegionNoWait = glow::QUERY_BY_REGION_NO_WAIT as isize,
+}
+
+pub struct OcclusionQuery {
+    state: *mut PipelineState,
+    id: glow::Query,
+    kind: OcclusionQueryKind,
+    active: bool,
+}
+
+impl OcclusionQuery {
+    /// Creates new query, `kind` is replaced by the closest supported one.
+    pub fn new(
+        state: &mut PipelineState,
+        kind: OcclusionQueryKind,
+    ) -> Result<Self, FrameworkError> {
+        let capabilities = state.capabilities();
+        let kind = match kind {
+            OcclusionQueryKind::SamplesPassed if capabilities.is_gles() => {
+                OcclusionQueryKind::AnySamplesPassed
+            }
+            OcclusionQueryKind::AnySamplesPassedConservative
+                if !capabilities.supports_conservative_occlusion_query() =>
+            {
+                OcclusionQueryKind::AnySamplesPassed
+            }
+            kind => kind,
+        };
+
+        let id = unsafe { state.gl.create_query()? };
+
+        Ok(Self {
+            state,
+            id,
+            kind,
+            active: false,
+        })
+    }
+
+    /// Actual kind of the query.
+    pub fn kind(&self) -> OcclusionQueryKind {
+        self.kind
+    }
+
+    /// Starts counting samples, only one occlusion query can be active at a time.
+    pub fn begin(&mut self, state: &mut PipelineState) {
+        if !self.active {
+            self.active = true;
+
+            unsafe {
+                state.gl.begin_query(self.kind.gl_target(), self.id);
+            }
+        }
+    }
+
+    pub fn end(&mut self, state: &mut PipelineState) {
+        if self.active {
+            self.active = false;
+
+            unsafe {
+                state.gl.end_query(self.kind.gl_target());
+            }
+        }
+    }
+
+    /// Draws a proxy geometry inside of the query. Draw parameters of the proxy (usually
+    /// disabled color and depth writes) must be applied by the caller.
+    pub fn draw_proxy(&mut self, binding: &mut GeometryBufferBinding) -> DrawCallStatistics {
+        self.begin(binding.state());
+        let statistics = binding.draw();
+        self.end(binding.state());
+        statistics
+    }
+
+    /// Returns result of the query if it is available, never blocks. It is the amount of
+    /// passed samples for `SamplesPassed`, and 0 or 1 for other kinds.
+    pub fn try_result(&self, state: &PipelineState) -> Option<u32> {
+        unsafe {
+            if state
+                .gl
+                .get_query_parameter_u32(self.id, glow::QUERY_RESULT_AVAILABLE)
+                != 0
+            {
+                Some(state.gl.get_query_parameter_u32(self.id, glow::QUERY_RESULT))
+            } else {
+                None
+            }
+        }
+    }
+
+    /// Returns true if any sample passed, `None` if the result is not ready yet.
+    pub fn is_visible(&self, state: &PipelineState) -> Option<bool> {
+        self.try_result(state).map(|samples| samples > 0)
+    }
+
+    /// Runs `func` with conditional rendering enabled: draw calls made inside of it are
+    /// discarded by GPU if no samples passed in this query. If conditional rendering is not
+    /// supported (it is never supported by WebGL), `func` is executed with unconditional
+    /// rendering.
+    pub fn conditional_render<R>(
+        &self,
+        state: &mut PipelineState,
+        mode: ConditionalRenderMode,
+        func: impl FnOnce(&mut PipelineState) -> R,
+    ) -> R {
+        #[cfg(target_arch = "wasm32")]
+        {
+            let _ = mode;
+            func(state)
+        }
+
+        #[cfg(not(target_arch = "wasm32"))]
+        {
+            if !state.capabilities().supports_conditional_render() {
+                return func(state);
+            }
+
+            unsafe {
+                state
+                    .gl
+                    .raw
+                    .BeginConditionalRender(query_name(self.id), mode as u32);
+            }
+
+            let result = func(state);
+
+            unsafe {
+                state.gl.raw.EndConditionalRender();
+            }
+
+            result
+        }
+    }
+}
+
+impl Drop for OcclusionQuery {
+    fn drop(&mut self) {
+        unsafe {
+            let state = &mut *self.state;
+            if self.active {
+                state.gl.end_query(self.kind.gl_target());
+            }
+            state.gl.delete_query(self.id);
+        }
+    }
+}
@@ -1,93 +1,1041 @@
 use glow::HasContext;
 use crate::core::color::Color;
-use std::fmt::Display;
+use crate::core::algebra::Vector4;
+use crate::core::math::Rect;
+use crate::geometry_buffer::{
+    BlendFunc, ClipDepth, ColorMask, CompareFunc, CullFace, DepthRange, DrawParameters,
+    PolygonFillMode, PolygonOffset, StencilFunc, StencilOp,
+};
+use crate::capabilities::GlCapabilities;
+use crate::shader::ShaderTarget;
+use crate::{Log, MessageKind};
+use crate::geometry_buffer::DrawCallStatistics;
+use std::cell::Cell;
+use std::collections::VecDeque;
+use std::fmt::{Debug, Display};
+#[cfg(not(target_arch = "wasm32"))]
+use std::num::NonZeroU32;
+use std::ops::{Deref, DerefMut};
 pub struct PipelineState {
     pub gl: glow::Context,
-    vao: Option<glow::VertexArray>,
-    frame_statistics: PipelineStatistics,
-    vbo: Option<glow::Buffer>,
-    program: Option<glow::Program>,
-    texture_units: [TextureUnit; 32],
-    clear_color: Color,
-
+    // Draw calls are registered through shared references of bindings, hence the `Cell`.
+    pub(crate) frame_statistics: Cell<PipelineStatistics>,
+    statistics_history: StatisticsHistory,
+    tracked: TrackedState,
+    state_stack: Vec<TrackedState>,
+    validation: StateValidation,
+    shader_target: ShaderTarget,
+    capabilities: GlCapabilities,
 }
 use std::fmt::Formatter;
 
 impl PipelineState{
     pub fn set_texture(&mut self, sampler_index: u32, target: u32, texture: Option<glow::Texture>) {
-        let unit = self.texture_units.get_mut(sampler_index as usize).unwrap();
-
-        if unit.target != target || unit.texture != texture {
-            unit.texture = texture;
-            unit.target = target;
+        let unit = self
+            .tracked
+            .texture_units
+            .get_mut(sampler_index as usize)
+            .unwrap();
 
-            self.frame_statistics.texture_binding_changes += 1;
+        if unit.set(TextureUnit { target, texture }) {
+            self.frame_statistics.get_mut().texture_binding_changes += 1;
 
             unsafe {
                 self.gl.active_texture(glow::TEXTURE0 + sampler_index);
-                self.gl.bind_texture(target, unit.texture);
+                self.gl.bind_texture(target, texture);
             }
         }
     }
 
     pub fn new(context: glow::Context) -> Self {
+        let capabilities = GlCapabilities::from_context(&context);
+        let shader_target = ShaderTarget::from_capabilities(&capabilities);
+
+        // Initial viewport is set by the context to the size of the window.
+        let viewport = unsafe { query_viewport(&context) };
 
         Self {
             gl: context,
-            vao: Default::default(),
-            vbo: Default::default(),
             frame_statistics: Default::default(),
-            program: Default::default(),
-            texture_units: [Default::default(); 32],
-            clear_color: Color::from_rgba(0, 0, 0, 0),
+            statistics_history: StatisticsHistory::new(DEFAULT_STATISTICS_HISTORY_LEN),
+            tracked: TrackedState::initial(&capabilities, viewport),
+            state_stack: Default::default(),
+            validation: Default::default(),
+            shader_target,
+            capabilities,
         }
     }
     pub fn set_vertex_array_object(&mut self, vao: Option<glow::VertexArray>) {
-        if self.vao != vao {
-            self.vao = vao;
-
-            self.frame_statistics.vao_binding_changes += 1;
+        if self.tracked.vao.set(vao) {
+            self.frame_statistics.get_mut().vao_binding_changes += 1;
 
             unsafe {
-                self.gl.bind_vertex_array(self.vao);
+                self.gl.bind_vertex_array(vao);
             }
         }
     }
 
     pub fn set_program(&mut self, program: Option<glow::Program>) {
-        if self.program != program {
-            self.program = program;
-
-            self.frame_statistics.program_binding_changes += 1;
+        if self.tracked.program.set(program) {
+            self.frame_statistics.get_mut().program_binding_changes += 1;
 
             unsafe {
-                self.gl.use_program(self.program);
+                self.gl.use_program(program);
             }
         }
     }
 
     pub fn set_vertex_buffer_object(&mut self, vbo: Option<glow::Buffer>) {
-        if self.vbo != vbo {
-            self.vbo = vbo;
-
-            self.frame_statistics.vbo_binding_changes += 1;
+        if self.tracked.vbo.set(vbo) {
+            self.frame_statistics.get_mut().vbo_binding_changes += 1;
 
             unsafe {
-                self.gl.bind_buffer(glow::ARRAY_BUFFER, self.vbo);
+                self.gl.bind_buffer(glow::ARRAY_BUFFER, vbo);
             }
         }
     }
 
-    pub fn set_clear_color(&mut self, color: Color) {
-        if self.clear_color != color {
-            self.clear_color = color;
+    pub fn capabilities(&self) -> &GlCapabilities {
+        &self.capabilities
+    }
+
+    pub fn shader_target(&self) -> ShaderTarget {
+        self.shader_target
+    }
+
+    /// Returns statistics of the current frame (since last `begin_frame`).
+    pub fn pipeline_statistics(&self) -> PipelineStatistics {
+        self.frame_statistics.get()
+    }
+
+    /// Resets per-frame statistics, must be called before rendering of a frame.
+    pub fn begin_frame(&mut self) {
+        self.frame_statistics.set(Default::default());
+    }
+
+    /// Finishes the frame: statistics of the frame are put into the history and returned.
+    pub fn end_frame(&mut self) -> PipelineStatistics {
+        let statistics = self.frame_statistics.get();
+        self.statistics_history.push(statistics);
+        statistics
+    }
+
+    pub(crate) fn register_draw_call(&self, draw_call: DrawCallStatistics) {
+        let mut statistics = self.frame_statistics.get();
+        statistics.register_draw_call(draw_call);
+        self.frame_statistics.set(statistics);
+    }
+
+    /// Returns statistics of last finished frames.
+    pub fn statistics_history(&self) -> &StatisticsHistory {
+        &self.statistics_history
+    }
+
+    /// Sets amount of frames kept in the statistics history, oldest frames are discarded.
+    pub fn set_statistics_history_len(&mut self, len: usize) {
+        self.statistics_history.set_capacity(len);
+    }
+
+    /// Forgets all cached state, so every next setter call reaches GL. Must be called after
+    /// any code that changes GL state bypassing `PipelineState` (UI libraries sharing the
+    /// context for example).
+    pub fn invalidate(&mut self) {
+        self.tracked = TrackedState::unknown(&self.capabilities);
+    }
+
+    /// Invalidates the cache and reads current bindings (program, VAO, array buffer, texture
+    /// of every unit) and simple fixed-function values back from GL. State that cannot be
+    /// mapped back (blend factors, compare functions, etc.) stays unknown and is applied on
+    /// next use. Querying GL stalls the pipeline, so this should not be done every frame.
+    ///
+    /// WebGL returns objects instead of names, bindings cannot be mapped back to handles
+    /// there and stay unknown.
+    pub fn resync_from_gl(&mut self) {
+        let previous_units = std::mem::take(&mut self.tracked.texture_units);
+        self.invalidate();
+
+        #[cfg(not(target_arch = "wasm32"))]
+        unsafe {
+            self.resync_bindings_from_gl(previous_units);
+        }
+        #[cfg(target_arch = "wasm32")]
+        drop(previous_units);
+
+        unsafe {
+            let gl = &self.gl;
+            let tracked = &mut self.tracked;
+
+            tracked.viewport = Cached::new(query_viewport(gl));
+            tracked.scissor = Cached::new(if gl.is_enabled(glow::SCISSOR_TEST) {
+                let mut scissor = [0; 4];
+                gl.get_parameter_i32_slice(glow::SCISSOR_BOX, &mut scissor);
+                Some(Rect::new(scissor[0], scissor[1], scissor[2], scissor[3]))
+            } else {
+                None
+            });
+            tracked.culling = Cached::new(gl.is_enabled(glow::CULL_FACE));
+            tracked.depth_test = Cached::new(gl.is_enabled(glow::DEPTH_TEST));
+            tracked.stencil_test = Cached::new(gl.is_enabled(glow::STENCIL_TEST));
+            tracked.depth_write = Cached::new(gl.get_parameter_i32(glow::DEPTH_WRITEMASK) != 0);
+            tracked.stencil_mask = [
+                Cached::new(gl.get_parameter_i32(glow::STENCIL_WRITEMASK) as u32),
+                Cached::new(gl.get_parameter_i32(glow::STENCIL_BACK_WRITEMASK) as u32),
+            ];
+            let mut color_write = [0; 4];
+            gl.get_parameter_i32_slice(glow::COLOR_WRITEMASK, &mut color_write);
+            tracked.color_write = Cached::new(ColorMask {
+                red: color_write[0] != 0,
+                green: color_write[1] != 0,
+                blue: color_write[2] != 0,
+                alpha: color_write[3] != 0,
+            });
+            tracked.clear_depth = Cached::new(gl.get_parameter_f32(glow::DEPTH_CLEAR_VALUE));
+            tracked.clear_stencil = Cached::new(gl.get_parameter_i32(glow::STENCIL_CLEAR_VALUE));
+            tracked.line_width = Cached::new(gl.get_parameter_f32(glow::LINE_WIDTH));
+            if self.capabilities.supports_depth_clamp() {
+                tracked.depth_clamp = Cached::new(gl.is_enabled(glow::DEPTH_CLAMP));
+            }
+        }
+    }
+
+    // Reads bindings of program, VAO, array buffer and texture units. Handles of the native
+    // backend are plain wrappers over names, so they can be restored from glGet* results.
+    #[cfg(not(target_arch = "wasm32"))]
+    unsafe fn resync_bindings_from_gl(&mut self, previous_units: Vec<Cached<TextureUnit>>) {
+        let gl = &self.gl;
+        let tracked = &mut self.tracked;
+
+        tracked.program = Cached::new(object_from_name(
+            gl.get_parameter_i32(glow::CURRENT_PROGRAM),
+            glow::NativeProgram,
+        ));
+        tracked.vao = Cached::new(object_from_name(
+            gl.get_parameter_i32(glow::VERTEX_ARRAY_BINDING),
+            glow::NativeVertexArray,
+        ));
+        tracked.vbo = Cached::new(object_from_name(
+            gl.get_parameter_i32(glow::ARRAY_BUFFER_BINDING),
+            glow::NativeBuffer,
+        ));
+
+        // Every unit can have a texture bound to each target, only the target we used last
+        // time is queried.
+        let active_texture = gl.get_parameter_i32(glow::ACTIVE_TEXTURE) as u32;
+        for (index, (unit, previous)) in tracked
+            .texture_units
+            .iter_mut()
+            .zip(previous_units)
+            .enumerate()
+        {
+            let target = previous.get().map_or(glow::TEXTURE_2D, |unit| unit.target);
+            if let Some(binding) = texture_binding_parameter(target) {
+                gl.active_texture(glow::TEXTURE0 + index as u32);
+                let texture = object_from_name(gl.get_parameter_i32(binding), glow::NativeTexture);
+                *unit = Cached::new(TextureUnit { target, texture });
+            }
+        }
+        gl.active_texture(active_texture);
+    }
+
+    /// Sets how divergence of the cache from actual GL state is reported. Validation is done
+    /// before every draw call and queries GL, so it is very slow and meant for debugging only.
+    pub fn set_state_validation(&mut self, validation: StateValidation) {
+        self.validation = validation;
+    }
+
+    pub fn state_validation(&self) -> StateValidation {
+        self.validation
+    }
+
+    /// Queries GL for the state that the cache believes is current (bindings of program,
+    /// VAO, array buffer and every texture unit, fixed-function flags and viewport) and
+    /// returns every mismatch. Unknown (invalidated) values are not checked. Usually it
+    /// means that some code changed state through `gl` directly. Bindings are not checked on
+    /// WebGL.
+    pub fn find_state_divergences(&self) -> Vec<StateDivergence> {
+        let mut divergences = Vec::new();
+        let tracked = &self.tracked;
+
+        #[cfg(not(target_arch = "wasm32"))]
+        self.find_binding_divergences(&mut divergences);
+
+        unsafe {
+            let gl = &self.gl;
+
+            // Global query of a capability returns state of draw buffer 0.
+            check_divergence(&mut divergences, "blend", tracked.blend_enabled[0].get(), || {
+                gl.is_enabled(glow::BLEND)
+            });
+            check_divergence(&mut divergences, "depth test", tracked.depth_test.get(), || {
+                gl.is_enabled(glow::DEPTH_TEST)
+            });
+            check_divergence(
+                &mut divergences,
+                "depth func",
+                tracked.depth_func.get().map(|func| func as u32),
+                || gl.get_parameter_i32(glow::DEPTH_FUNC) as u32,
+            );
+            check_divergence(&mut divergences, "depth write", tracked.depth_write.get(), || {
+                gl.get_parameter_i32(glow::DEPTH_WRITEMASK) != 0
+            });
+            check_divergence(&mut divergences, "stencil test", tracked.stencil_test.get(), || {
+                gl.is_enabled(glow::STENCIL_TEST)
+            });
+            check_divergence(&mut divergences, "culling", tracked.culling.get(), || {
+                gl.is_enabled(glow::CULL_FACE)
+            });
+            check_divergence(
+                &mut divergences,
+                "cull face",
+                tracked.cull_face.get().map(|face| face as u32),
+                || gl.get_parameter_i32(glow::CULL_FACE_MODE) as u32,
+            );
+            check_divergence(
+                &mut divergences,
+                "scissor test",
+                tracked.scissor.get().map(|scissor| scissor.is_some()),
+                || gl.is_enabled(glow::SCISSOR_TEST),
+            );
+            check_divergence(&mut divergences, "viewport", tracked.viewport.get(), || {
+                query_viewport(gl)
+            });
+        }
+
+        divergences
+    }
+
+    // Bindings are compared by names, which are available on the native backend only.
+    #[cfg(not(target_arch = "wasm32"))]
+    fn find_binding_divergences(&self, divergences: &mut Vec<StateDivergence>) {
+        let tracked = &self.tracked;
+
+        unsafe {
+            let gl = &self.gl;
+
+            check_divergence(divergences, "program", tracked.program.get(), || {
+                object_from_name(
+                    gl.get_parameter_i32(glow::CURRENT_PROGRAM),
+                    glow::NativeProgram,
+                )
+            });
+            check_divergence(divergences, "vertex array", tracked.vao.get(), || {
+                object_from_name(
+                    gl.get_parameter_i32(glow::VERTEX_ARRAY_BINDING),
+                    glow::NativeVertexArray,
+                )
+            });
+            check_divergence(divergences, "array buffer", tracked.vbo.get(), || {
+                object_from_name(
+                    gl.get_parameter_i32(glow::ARRAY_BUFFER_BINDING),
+                    glow::NativeBuffer,
+                )
+            });
+
+            let active_texture = gl.get_parameter_i32(glow::ACTIVE_TEXTURE) as u32;
+            for (index, unit) in tracked.texture_units.iter().enumerate() {
+                if let Some(unit) = unit.get() {
+                    if let Some(binding) = texture_binding_parameter(unit.target) {
+                        check_divergence(
+                            divergences,
+                            &format!("texture unit {}", index),
+                            Some(unit.texture),
+                            || {
+                                gl.active_texture(glow::TEXTURE0 + index as u32);
+                                object_from_name(
+                                    gl.get_parameter_i32(binding),
+                                    glow::NativeTexture,
+                                )
+                            },
+                        );
+                    }
+                }
+            }
+            gl.active_texture(active_texture);
+        }
+    }
 
+    // Called before every draw call.
+    pub(crate) fn validate_state(&self) {
+        if self.validation == StateValidation::Disabled {
+            return;
+        }
+
+        let divergences = self.find_state_divergences();
+        if divergences.is_empty() {
+            return;
+        }
+
+        let message = divergences
+            .iter()
+            .map(|divergence| divergence.to_string())
+            .collect::<Vec<_>>()
+            .join("\n\t");
+
+        match self.validation {
+            StateValidation::Log => Log::writeln(
+                MessageKind::Error,
+                format!("Pipeline state diverged from GL state:\n\t{}", message),
+            ),
+            StateValidation::Panic => {
+                panic!("Pipeline state diverged from GL state:\n\t{}", message)
+            }
+            StateValidation::Disabled => (),
+        }
+    }
+
+    /// Saves all tracked state (bindings and fixed-function values) on a stack, it can be
+    /// restored later by `pop_state`. Use `scoped_state` if the restore point is known
+    /// statically.
+    pub fn push_state(&mut self) {
+        self.state_stack.push(self.tracked.clone());
+    }
+
+    /// Restores state saved by the last `push_state`. Every saved value is applied to GL
+    /// regardless of the cache, because the state could be changed by a third party in the
+    /// meantime. Values that were unknown at the moment of push stay unknown. Does nothing
+    /// if the stack is empty.
+    pub fn pop_state(&mut self) {
+        if let Some(saved) = self.state_stack.pop() {
+            self.invalidate();
+            self.restore(&saved);
+        }
+    }
+
+    /// Pushes state and returns a guard which pops it when dropped. The guard dereferences
+    /// to the pipeline state, so it can be passed to the code that changes it.
+    pub fn scoped_state(&mut self) -> PipelineStateGuard<'_> {
+        self.push_state();
+        PipelineStateGuard { state: self }
+    }
+
+    fn restore(&mut self, saved: &TrackedState) {
+        if let Some(program) = saved.program.get() {
+            self.set_program(program);
+        }
+        if let Some(vao) = saved.vao.get() {
+            self.set_vertex_array_object(vao);
+        }
+        if let Some(vbo) = saved.vbo.get() {
+            self.set_vertex_buffer_object(vbo);
+        }
+        for (index, unit) in saved.texture_units.iter().enumerate() {
+            if let Some(unit) = unit.get() {
+                self.set_texture(index as u32, unit.target, unit.texture);
+            }
+        }
+
+        if let Some(color) = saved.clear_color.get() {
+            self.set_clear_color(color);
+        }
+        if let Some(depth) = saved.clear_depth.get() {
+            self.set_clear_depth(depth);
+        }
+        if let Some(stencil) = saved.clear_stencil.get() {
+            self.set_clear_stencil(stencil);
+        }
+        if let Some(color_write) = saved.color_write.get() {
+            self.set_color_write(color_write);
+        }
+        if let Some(depth_write) = saved.depth_write.get() {
+            self.set_depth_write(depth_write);
+        }
+        if let Some(viewport) = saved.viewport.get() {
+            self.set_viewport(viewport);
+        }
+        if let Some(scissor) = saved.scissor.get() {
+            self.set_scissor(scissor);
+        }
+        if let Some(culling) = saved.culling.get() {
+            self.set_culling(culling);
+        }
+        if let Some(cull_face) = saved.cull_face.get() {
+            self.set_cull_face(cull_face);
+        }
+        if let Some(depth_test) = saved.depth_test.get() {
+            self.set_depth_test(depth_test);
+        }
+        if let Some(depth_func) = saved.depth_func.get() {
+            self.set_depth_func(depth_func);
+        }
+        if let Some(stencil_test) = saved.stencil_test.get() {
+            self.set_stencil_test(stencil_test);
+        }
+        // Stencil op carries write mask, so masks are restored after ops.
+        for face in [StencilFace::Front, StencilFace::Back] {
+            if let Some(func) = saved.stencil_func[face.index()].get() {
+                self.set_stencil_func_separate(face, func);
+            }
+            if let Some(op) = saved.stencil_op[face.index()].get() {
+                self.set_stencil_op_separate(face, op);
+            }
+        }
+        for face in [StencilFace::Front, StencilFace::Back] {
+            if let Some(mask) = saved.stencil_mask[face.index()].get() {
+                self.set_stencil_mask_separate(face, mask);
+            }
+        }
+        if self.capabilities.supports_draw_buffer_blend() {
+            for draw_buffer in 0..saved.blend_enabled.len() {
+                if let Some(enabled) = saved.blend_enabled[draw_buffer].get() {
+                    self.set_draw_buffer_blend_enabled(draw_buffer, enabled);
+                }
+                if let Some(func) = saved.blend_func[draw_buffer].get() {
+                    self.set_draw_buffer_blend_func(draw_buffer, func);
+                }
+            }
+        } else {
+            if let Some(enabled) = saved.blend_enabled[0].get() {
+                self.set_blend(enabled);
+            }
+            if let Some(func) = saved.blend_func[0].get() {
+                self.set_blend_func(func);
+            }
+        }
+        if let Some(color) = saved.blend_color.get() {
+            self.set_blend_color(color);
+        }
+        if let Some(polygon_mode) = saved.polygon_mode.get() {
+            self.set_polygon_mode(polygon_mode);
+        }
+        if let Some(polygon_offset) = saved.polygon_offset.get() {
+            self.set_polygon_offset(polygon_offset);
+        }
+        if let Some(line_width) = saved.line_width.get() {
+            self.set_line_width(line_width);
+        }
+        if let Some(depth_clamp) = saved.depth_clamp.get() {
+            self.set_depth_clamp(depth_clamp);
+        }
+        if let Some(depth_range) = saved.depth_range.get() {
+            self.set_depth_range(depth_range);
+        }
+        if let Some(clip_depth) = saved.clip_depth.get() {
+            self.set_clip_depth(clip_depth);
+        }
+        if let Some(restart_index) = saved.primitive_restart.get() {
+            self.set_primitive_restart(restart_index);
+        }
+    }
+
+    pub fn set_clear_color(&mut self, color: Color) {
+        if self.tracked.clear_color.set(color) {
             let rgba = color.as_frgba();
             unsafe {
                 self.gl.clear_color(rgba.x, rgba.y, rgba.z, rgba.w);
             }
         }
     }
+
+    pub fn set_clear_depth(&mut self, depth: f32) {
+        if self.tracked.clear_depth.set(depth) {
+            unsafe {
+                self.gl.clear_depth_f32(depth);
+            }
+        }
+    }
+
+    pub fn set_clear_stencil(&mut self, stencil: i32) {
+        if self.tracked.clear_stencil.set(stencil) {
+            unsafe {
+                self.gl.clear_stencil(stencil);
+            }
+        }
+    }
+
+    pub fn set_color_write(&mut self, color_write: ColorMask) {
+        if self.tracked.color_write.set(color_write) {
+            unsafe {
+                self.gl.color_mask(
+                    color_write.red,
+                    color_write.green,
+                    color_write.blue,
+                    color_write.alpha,
+                );
+            }
+        }
+    }
+
+    pub fn set_depth_write(&mut self, depth_write: bool) {
+        if self.tracked.depth_write.set(depth_write) {
+            unsafe {
+                self.gl.depth_mask(depth_write);
+            }
+        }
+    }
+
+    pub fn set_stencil_mask(&mut self, stencil_mask: u32) {
+        let [front, back] = &mut self.tracked.stencil_mask;
+        if front.set(stencil_mask) | back.set(stencil_mask) {
+            unsafe {
+                self.gl.stencil_mask(stencil_mask);
+            }
+        }
+    }
+
+    pub fn set_stencil_mask_separate(&mut self, face: StencilFace, stencil_mask: u32) {
+        if self.tracked.stencil_mask[face.index()].set(stencil_mask) {
+            unsafe {
+                self.gl.stencil_mask_separate(face.gl_face(), stencil_mask);
+            }
+        }
+    }
+
+    pub fn set_viewport(&mut self, viewport: Rect<i32>) {
+        if self.tracked.viewport.set(viewport) {
+            unsafe {
+                self.gl.viewport(
+                    viewport.position.x,
+                    viewport.position.y,
+                    viewport.size.x,
+                    viewport.size.y,
+                );
+            }
+        }
+    }
+
+    pub fn viewport(&self) -> Rect<i32> {
+        self.tracked
+            .viewport
+            .get()
+            .unwrap_or_else(|| unsafe { query_viewport(&self.gl) })
+    }
+
+    /// Sets scissor rectangle, `None` disables scissor test.
+    pub fn set_scissor(&mut self, scissor: Option<Rect<i32>>) {
+        let previous = self.tracked.scissor.get();
+        if self.tracked.scissor.set(scissor) {
+            unsafe {
+                match scissor {
+                    Some(rect) => {
+                        if !matches!(previous, Some(Some(_))) {
+                            self.gl.enable(glow::SCISSOR_TEST);
+                        }
+                        self.gl.scissor(
+                            rect.position.x,
+                            rect.position.y,
+                            rect.size.x,
+                            rect.size.y,
+                        );
+                    }
+                    None => self.gl.disable(glow::SCISSOR_TEST),
+                }
+            }
+        }
+    }
+
+    /// Clears buffers of currently bound framebuffer, `None` leaves respective buffer intact.
+    /// GL does not touch channels that are masked out for writing, so write masks of the
+    /// buffers being cleared are enabled first (through the cache, next draw call restores
+    /// its own masks). Clear is limited by the scissor rectangle if it is set.
+    pub fn clear(&mut self, color: Option<Color>, depth: Option<f32>, stencil: Option<i32>) {
+        let mut mask = 0;
+
+        if let Some(color) = color {
+            self.set_clear_color(color);
+            self.set_color_write(ColorMask::all(true));
+            mask |= glow::COLOR_BUFFER_BIT;
+        }
+
+        if let Some(depth) = depth {
+            self.set_clear_depth(depth);
+            self.set_depth_write(true);
+            mask |= glow::DEPTH_BUFFER_BIT;
+        }
+
+        if let Some(stencil) = stencil {
+            self.set_clear_stencil(stencil);
+            self.set_stencil_mask(0xFFFF_FFFF);
+            mask |= glow::STENCIL_BUFFER_BIT;
+        }
+
+        if mask != 0 {
+            unsafe {
+                self.gl.clear(mask);
+            }
+        }
+    }
+
+    pub fn set_culling(&mut self, culling: bool) {
+        if self.tracked.culling.set(culling) {
+            unsafe {
+                set_capability(&self.gl, glow::CULL_FACE, culling);
+            }
+        }
+    }
+
+    pub fn set_cull_face(&mut self, cull_face: CullFace) {
+        if self.tracked.cull_face.set(cull_face) {
+            unsafe {
+                self.gl.cull_face(cull_face as u32);
+            }
+        }
+    }
+
+    pub fn set_depth_test(&mut self, depth_test: bool) {
+        if self.tracked.depth_test.set(depth_test) {
+            unsafe {
+                set_capability(&self.gl, glow::DEPTH_TEST, depth_test);
+            }
+        }
+    }
+
+    pub fn set_depth_func(&mut self, depth_func: CompareFunc) {
+        if self.tracked.depth_func.set(depth_func) {
+            unsafe {
+                self.gl.depth_func(depth_func as u32);
+            }
+        }
+    }
+
+    pub fn set_stencil_test(&mut self, stencil_test: bool) {
+        if self.tracked.stencil_test.set(stencil_test) {
+            unsafe {
+                set_capability(&self.gl, glow::STENCIL_TEST, stencil_test);
+            }
+        }
+    }
+
+    pub fn set_stencil_func(&mut self, func: StencilFunc) {
+        let [front, back] = &mut self.tracked.stencil_func;
+        if front.set(func) | back.set(func) {
+            unsafe {
+                self.gl
+                    .stencil_func(func.func as u32, func.ref_value as i32, func.mask);
+            }
+        }
+    }
+
+    pub fn set_stencil_func_separate(&mut self, face: StencilFace, func: StencilFunc) {
+        if self.tracked.stencil_func[face.index()].set(func) {
+            unsafe {
+                self.gl.stencil_func_separate(
+                    face.gl_face(),
+                    func.func as u32,
+                    func.ref_value as i32,
+                    func.mask,
+                );
+            }
+        }
+    }
+
+    pub fn set_stencil_op(&mut self, op: StencilOp) {
+        let [front, back] = &mut self.tracked.stencil_op;
+        if front.set(op) | back.set(op) {
+            unsafe {
+                self.gl
+                    .stencil_op(op.fail as u32, op.zfail as u32, op.zpass as u32);
+            }
+        }
+
+        self.set_stencil_mask(op.write_mask);
+    }
+
+    pub fn set_stencil_op_separate(&mut self, face: StencilFace, op: StencilOp) {
+        if self.tracked.stencil_op[face.index()].set(op) {
+            unsafe {
+                self.gl.stencil_op_separate(
+                    face.gl_face(),
+                    op.fail as u32,
+                    op.zfail as u32,
+                    op.zpass as u32,
+                );
+            }
+        }
+
+        self.set_stencil_mask_separate(face, op.write_mask);
+    }
+
+    pub fn set_blend(&mut self, blend: bool) {
+        let mut changed = false;
+        for enabled in self.tracked.blend_enabled.iter_mut() {
+            changed |= enabled.set(blend);
+        }
+
+        if changed {
+            self.frame_statistics.get_mut().blend_state_changes += 1;
+
+            unsafe {
+                set_capability(&self.gl, glow::BLEND, blend);
+            }
+        }
+    }
+
+    pub fn set_blend_func(&mut self, func: BlendFunc) {
+        let mut changed = false;
+        for cached in self.tracked.blend_func.iter_mut() {
+            changed |= cached.set(func);
+        }
+
+        if changed {
+            self.frame_statistics.get_mut().blend_state_changes += 1;
+
+            unsafe {
+                self.gl.blend_func_separate(
+                    func.sfactor as u32,
+                    func.dfactor as u32,
+                    func.alpha_sfactor() as u32,
+                    func.alpha_dfactor() as u32,
+                );
+                self.gl
+                    .blend_equation_separate(func.equation as u32, func.alpha_equation() as u32);
+            }
+        }
+    }
+
+    /// Sets blend state of a single draw buffer, `None` disables blending for it. If the
+    /// context does not support per-buffer blending, state of buffer 0 is applied to all
+    /// buffers and other buffers are ignored.
+    pub fn set_draw_buffer_blend(&mut self, draw_buffer: u32, blend: Option<BlendFunc>) {
+        if !self.capabilities.supports_draw_buffer_blend() {
+            if draw_buffer == 0 {
+                if let Some(func) = blend {
+                    self.set_blend_func(func);
+                }
+                self.set_blend(blend.is_some());
+            }
+            return;
+        }
+
+        let draw_buffer = draw_buffer as usize;
+        if draw_buffer >= self.tracked.blend_enabled.len() {
+            return;
+        }
+
+        self.set_draw_buffer_blend_enabled(draw_buffer, blend.is_some());
+        if let Some(func) = blend {
+            self.set_draw_buffer_blend_func(draw_buffer, func);
+        }
+    }
+
+    fn set_draw_buffer_blend_enabled(&mut self, draw_buffer: usize, enabled: bool) {
+        if self.tracked.blend_enabled[draw_buffer].set(enabled) {
+            self.frame_statistics.get_mut().blend_state_changes += 1;
+
+            unsafe {
+                if enabled {
+                    self.gl.enable_draw_buffer(glow::BLEND, draw_buffer as u32);
+                } else {
+                    self.gl.disable_draw_buffer(glow::BLEND, draw_buffer as u32);
+                }
+            }
+        }
+    }
+
+    fn set_draw_buffer_blend_func(&mut self, draw_buffer: usize, func: BlendFunc) {
+        if self.tracked.blend_func[draw_buffer].set(func) {
+            self.frame_statistics.get_mut().blend_state_changes += 1;
+
+            unsafe {
+                self.gl.blend_func_separate_draw_buffer(
+                    draw_buffer as u32,
+                    func.sfactor as u32,
+                    func.dfactor as u32,
+                    func.alpha_sfactor() as u32,
+                    func.alpha_dfactor() as u32,
+                );
+                self.gl.blend_equation_separate_draw_buffer(
+                    draw_buffer as u32,
+                    func.equation as u32,
+                    func.alpha_equation() as u32,
+                );
+            }
+        }
+    }
+
+    pub fn set_blend_color(&mut self, color: Vector4<f32>) {
+        if self.tracked.blend_color.set(color) {
+            self.frame_statistics.get_mut().blend_state_changes += 1;
+
+            unsafe {
+                self.gl.blend_color(color.x, color.y, color.z, color.w);
+            }
+        }
+    }
+
+    /// Sets polygon rasterization mode for both faces. Ignored if the context does not
+    /// support `glPolygonMode` (GLES).
+    pub fn set_polygon_mode(&mut self, polygon_mode: PolygonFillMode) {
+        if self.capabilities.supports_polygon_mode() && self.tracked.polygon_mode.set(polygon_mode)
+        {
+            unsafe {
+                self.gl
+                    .polygon_mode(glow::FRONT_AND_BACK, polygon_mode as u32);
+            }
+        }
+    }
+
+    /// Sets polygon offset, `None` disables it. The offset is applied in every polygon mode
+    /// (filled polygons, lines and points).
+    pub fn set_polygon_offset(&mut self, polygon_offset: Option<PolygonOffset>) {
+        let previous = self.tracked.polygon_offset.get();
+        if self.tracked.polygon_offset.set(polygon_offset) {
+            // Line and point offsets exist only where polygon mode can be changed.
+            let capabilities: &[u32] = if self.capabilities.supports_polygon_mode() {
+                &[
+                    glow::POLYGON_OFFSET_FILL,
+                    glow::POLYGON_OFFSET_LINE,
+                    glow::POLYGON_OFFSET_POINT,
+                ]
+            } else {
+                &[glow::POLYGON_OFFSET_FILL]
+            };
+
+            unsafe {
+                match polygon_offset {
+                    Some(offset) => {
+                        if !matches!(previous, Some(Some(_))) {
+                            for &capability in capabilities {
+                                self.gl.enable(capability);
+                            }
+                        }
+                        self.gl.polygon_offset(offset.factor, offset.units);
+                    }
+                    None => {
+                        for &capability in capabilities {
+                            self.gl.disable(capability);
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    pub fn set_line_width(&mut self, line_width: f32) {
+        if self.tracked.line_width.set(line_width) {
+            unsafe {
+                self.gl.line_width(line_width);
+            }
+        }
+    }
+
+    /// Enables or disables depth clamping. Ignored if the context does not support it.
+    pub fn set_depth_clamp(&mut self, depth_clamp: bool) {
+        if self.capabilities.supports_depth_clamp() && self.tracked.depth_clamp.set(depth_clamp) {
+            unsafe {
+                set_capability(&self.gl, glow::DEPTH_CLAMP, depth_clamp);
+            }
+        }
+    }
+
+    pub fn set_depth_range(&mut self, depth_range: DepthRange) {
+        if self.tracked.depth_range.set(depth_range) {
+            unsafe {
+                self.gl.depth_range_f32(depth_range.near, depth_range.far);
+            }
+        }
+    }
+
+    /// Sets clip space depth convention. Ignored if `glClipControl` is not available (always
+    /// on WebGL), in this case depth is always in `[-1; 1]` range.
+    pub fn set_clip_depth(&mut self, clip_depth: ClipDepth) {
+        #[cfg(not(target_arch = "wasm32"))]
+        if self.capabilities.supports_clip_control() && self.tracked.clip_depth.set(clip_depth) {
+            unsafe {
+                self.gl.raw.ClipControl(glow::LOWER_LEFT, clip_depth as u32);
+            }
+        }
+
+        #[cfg(target_arch = "wasm32")]
+        let _ = clip_depth;
+    }
+
+    /// Enables primitive restart, `restart_index` must be the maximum value of the index
+    /// type. `None` disables primitive restart.
+    ///
+    /// WebGL 2 always restarts primitives at the maximum index and does not allow to toggle
+    /// it, so this call does nothing there.
+    pub fn set_primitive_restart(&mut self, restart_index: Option<u32>) {
+        #[cfg(target_arch = "wasm32")]
+        let _ = restart_index;
+
+        #[cfg(not(target_arch = "wasm32"))]
+        if self.tracked.primitive_restart.set(restart_index) {
+            unsafe {
+                if self.capabilities.supports_fixed_index_primitive_restart() {
+                    set_capability(
+                        &self.gl,
+                        glow::PRIMITIVE_RESTART_FIXED_INDEX,
+                        restart_index.is_some(),
+                    );
+                } else {
+                    match restart_index {
+                        Some(index) => {
+                            self.gl.enable(glow::PRIMITIVE_RESTART);
+                            self.gl.raw.PrimitiveRestartIndex(index);
+                        }
+                        None => self.gl.disable(glow::PRIMITIVE_RESTART),
+                    }
+                }
+            }
+        }
+    }
+
+    pub fn apply_draw_parameters(&mut self, draw_params: &DrawParameters) {
+        if draw_params.draw_buffer_blend.is_empty() {
+            if let Some(blend_func) = draw_params.blend {
+                self.set_blend_func(blend_func);
+                self.set_blend(true);
+            } else {
+                self.set_blend(false);
+            }
+        } else {
+            for draw_buffer in 0..self.tracked.blend_enabled.len() {
+                let blend = draw_params
+                    .draw_buffer_blend
+                    .get(draw_buffer)
+                    .copied()
+                    .unwrap_or(draw_params.blend);
+                self.set_draw_buffer_blend(draw_buffer as u32, blend);
+            }
+        }
+        self.set_blend_color(draw_params.blend_color);
+
+        self.set_depth_test(draw_params.depth_test);
+        self.set_depth_func(draw_params.depth_func);
+        self.set_depth_write(draw_params.depth_write);
+        self.set_color_write(draw_params.color_write);
+
+        if let Some(back_stencil) = draw_params.back_stencil {
+            self.set_stencil_test(true);
+            self.set_stencil_func_separate(
+                StencilFace::Front,
+                draw_params.stencil_test.unwrap_or_default(),
+            );
+            self.set_stencil_func_separate(StencilFace::Back, back_stencil.func);
+            self.set_stencil_op_separate(StencilFace::Front, draw_params.stencil_op);
+            self.set_stencil_op_separate(StencilFace::Back, back_stencil.op);
+        } else {
+            if let Some(stencil_func) = draw_params.stencil_test {
+                self.set_stencil_test(true);
+                self.set_stencil_func(stencil_func);
+            } else {
+                self.set_stencil_test(false);
+            }
+            self.set_stencil_op(draw_params.stencil_op);
+        }
+
+        if let Some(cull_face) = draw_params.cull_face {
+            self.set_cull_face(cull_face);
+            self.set_culling(true);
+        } else {
+            self.set_culling(false);
+        }
+
+        self.set_polygon_mode(draw_params.polygon_mode);
+        self.set_polygon_offset(draw_params.polygon_offset);
+        self.set_line_width(draw_params.line_width);
+        self.set_depth_clamp(draw_params.depth_clamp);
+        self.set_depth_range(draw_params.depth_range);
+        self.set_clip_depth(draw_params.clip_depth);
+    }
+}
+
+unsafe fn set_capability(gl: &glow::Context, capability: u32, enabled: bool) {
+    if enabled {
+        gl.enable(capability);
+    } else {
+        gl.disable(capability);
+    }
 }
 
 
@@ -99,6 +1047,51 @@ pub struct PipelineStatistics {
     pub blend_state_changes: usize,
     pub framebuffer_binding_changes: usize,
     pub program_binding_changes: usize,
+    pub uniform_uploads: usize,
+    pub uniform_uploads_skipped: usize,
+    pub draw_calls: usize,
+    pub triangles: usize,
+    pub instances: usize,
+    /// Amount of bytes sent to GPU: buffer data and uniforms.
+    pub bytes_uploaded: usize,
+}
+
+impl PipelineStatistics {
+    pub(crate) fn register_draw_call(&mut self, draw_call: DrawCallStatistics) {
+        self.draw_calls += 1;
+        self.triangles += draw_call.triangles;
+        self.instances += draw_call.instances;
+    }
+
+    // Applies the function to every pair of respective counters.
+    fn zip_with(self, other: Self, func: impl Fn(usize, usize) -> usize) -> Self {
+        Self {
+            texture_binding_changes: func(
+                self.texture_binding_changes,
+                other.texture_binding_changes,
+            ),
+            vbo_binding_changes: func(self.vbo_binding_changes, other.vbo_binding_changes),
+            vao_binding_changes: func(self.vao_binding_changes, other.vao_binding_changes),
+            blend_state_changes: func(self.blend_state_changes, other.blend_state_changes),
+            framebuffer_binding_changes: func(
+                self.framebuffer_binding_changes,
+                other.framebuffer_binding_changes,
+            ),
+            program_binding_changes: func(
+                self.program_binding_changes,
+                other.program_binding_changes,
+            ),
+            uniform_uploads: func(self.uniform_uploads, other.uniform_uploads),
+            uniform_uploads_skipped: func(
+                self.uniform_uploads_skipped,
+                other.uniform_uploads_skipped,
+            ),
+            draw_calls: func(self.draw_calls, other.draw_calls),
+            triangles: func(self.triangles, other.triangles),
+            instances: func(self.instances, other.instances),
+            bytes_uploaded: func(self.bytes_uploaded, other.bytes_uploaded),
+        }
+    }
 }
 
 
@@ -112,19 +1105,334 @@ impl Display for PipelineStatistics {
             \tVAO: {},\n\
             \tFBO: {},\n\
             \tShaders: {},\n\
-            \tBlend: {}",
+            \tBlend: {},\n\
+            \tUniforms: {} (skipped: {})\n\
+            Draw calls: {}\n\
+            \tTriangles: {}\n\
+            \tInstances: {}\n\
+            Bytes uploaded: {}",
             self.texture_binding_changes,
             self.vbo_binding_changes,
             self.vao_binding_changes,
             self.framebuffer_binding_changes,
             self.program_binding_changes,
-            self.blend_state_changes
+            self.blend_state_changes,
+            self.uniform_uploads,
+            self.uniform_uploads_skipped,
+            self.draw_calls,
+            self.triangles,
+            self.instances,
+            self.bytes_uploaded
+        )
+    }
+}
+
+
+const DEFAULT_STATISTICS_HISTORY_LEN: usize = 120;
+
+/// Statistics of last N frames, oldest first.
+#[derive(Debug, Clone)]
+pub struct StatisticsHistory {
+    frames: VecDeque<PipelineStatistics>,
+    capacity: usize,
+}
+
+impl StatisticsHistory {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            frames: VecDeque::with_capacity(capacity),
+            capacity,
+        }
+    }
+
+    pub fn push(&mut self, frame: PipelineStatistics) {
+        if self.capacity == 0 {
+            return;
+        }
+        while self.frames.len() >= self.capacity {
+            self.frames.pop_front();
+        }
+        self.frames.push_back(frame);
+    }
+
+    pub fn set_capacity(&mut self, capacity: usize) {
+        self.capacity = capacity;
+        while self.frames.len() > capacity {
+            self.frames.pop_front();
+        }
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    pub fn len(&self) -> usize {
+        self.frames.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.frames.is_empty()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &PipelineStatistics> {
+        self.frames.iter()
+    }
+
+    /// Statistics of the last finished frame.
+    pub fn last(&self) -> Option<&PipelineStatistics> {
+        self.frames.back()
+    }
+
+    /// Average value of every counter over the history (rounded down).
+    pub fn average(&self) -> PipelineStatistics {
+        let count = self.frames.len().max(1);
+        self.sum().zip_with(Default::default(), |sum, _| sum / count)
+    }
+
+    /// Maximum value of every counter over the history, useful to detect spikes.
+    pub fn peak(&self) -> PipelineStatistics {
+        self.frames
+            .iter()
+            .fold(Default::default(), |peak, frame| peak.zip_with(*frame, usize::max))
+    }
+
+    fn sum(&self) -> PipelineStatistics {
+        self.frames
+            .iter()
+            .fold(Default::default(), |sum, frame| sum.zip_with(*frame, |a, b| a + b))
+    }
+}
+
+
+/// A face which stencil state is set separately.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum StencilFace {
+    Front,
+    Back,
+}
+
+impl StencilFace {
+    fn index(self) -> usize {
+        match self {
+            StencilFace::Front => 0,
+            StencilFace::Back => 1,
+        }
+    }
+
+    fn gl_face(self) -> u32 {
+        match self {
+            StencilFace::Front => glow::FRONT,
+            StencilFace::Back => glow::BACK,
+        }
+    }
+}
+
+
+
+/// Defines how [`PipelineState`] reacts to divergence of its cache from actual GL state.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum StateValidation {
+    /// State is not validated.
+    Disabled,
+    /// Divergences are written to the log.
+    Log,
+    /// Divergences cause panic, so they can be caught in tests or under debugger.
+    Panic,
+}
+
+impl Default for StateValidation {
+    fn default() -> Self {
+        Self::Disabled
+    }
+}
+
+/// A piece of state which cached value does not match actual GL state.
+#[derive(Clone, Debug)]
+pub struct StateDivergence {
+    /// Name of the state.
+    pub state: String,
+    /// Value that `PipelineState` believes is current.
+    pub cached: String,
+    /// Value returned by GL.
+    pub actual: String,
+}
+
+impl Display for StateDivergence {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}: cached {}, actual {}",
+            self.state, self.cached, self.actual
         )
     }
 }
 
+// Compares known cached value with the one queried from GL.
+fn check_divergence<T: PartialEq + Debug>(
+    divergences: &mut Vec<StateDivergence>,
+    state: &str,
+    cached: Option<T>,
+    query: impl FnOnce() -> T,
+) {
+    if let Some(cached) = cached {
+        let actual = query();
+        if actual != cached {
+            divergences.push(StateDivergence {
+                state: state.to_owned(),
+                cached: format!("{:?}", cached),
+                actual: format!("{:?}", actual),
+            });
+        }
+    }
+}
+
+/// Restores pipeline state saved by [`PipelineState::scoped_state`] when dropped.
+pub struct PipelineStateGuard<'a> {
+    state: &'a mut PipelineState,
+}
+
+impl Deref for PipelineStateGuard<'_> {
+    type Target = PipelineState;
+
+    fn deref(&self) -> &Self::Target {
+        self.state
+    }
+}
+
+impl DerefMut for PipelineStateGuard<'_> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        self.state
+    }
+}
+
+impl Drop for PipelineStateGuard<'_> {
+    fn drop(&mut self) {
+        self.state.pop_state();
+    }
+}
+
+
+// A value of GL state as we believe it is, `None` if it is unknown (state was invalidated),
+// in this case next setter call always reaches GL.
+#[derive(Copy, Clone, Debug)]
+struct Cached<T>(Option<T>);
+
+impl<T> Default for Cached<T> {
+    fn default() -> Self {
+        Self(None)
+    }
+}
+
+impl<T: Copy + PartialEq> Cached<T> {
+    fn new(value: T) -> Self {
+        Self(Some(value))
+    }
+
+    fn get(&self) -> Option<T> {
+        self.0
+    }
+
+    // Returns true if the value was changed (or was unknown) and GL must be updated.
+    fn set(&mut self, value: T) -> bool {
+        if self.0 != Some(value) {
+            self.0 = Some(value);
+            true
+        } else {
+            false
+        }
+    }
+}
+
+
+// Everything that PipelineState tracks, it is copied as a whole by push_state.
+#[derive(Clone, Default)]
+struct TrackedState {
+    vao: Cached<Option<glow::VertexArray>>,
+    vbo: Cached<Option<glow::Buffer>>,
+    program: Cached<Option<glow::Program>>,
+    texture_units: Vec<Cached<TextureUnit>>,
+    clear_color: Cached<Color>,
+    clear_depth: Cached<f32>,
+    clear_stencil: Cached<i32>,
+    color_write: Cached<ColorMask>,
+    depth_write: Cached<bool>,
+    // Per face: front, back.
+    stencil_mask: [Cached<u32>; 2],
+    viewport: Cached<Rect<i32>>,
+    scissor: Cached<Option<Rect<i32>>>,
+    culling: Cached<bool>,
+    cull_face: Cached<CullFace>,
+    depth_test: Cached<bool>,
+    depth_func: Cached<CompareFunc>,
+    stencil_test: Cached<bool>,
+    stencil_func: [Cached<StencilFunc>; 2],
+    stencil_op: [Cached<StencilOp>; 2],
+    // Blend state of every draw buffer, global blend calls change all of them.
+    blend_enabled: Vec<Cached<bool>>,
+    blend_func: Vec<Cached<BlendFunc>>,
+    blend_color: Cached<Vector4<f32>>,
+    polygon_mode: Cached<PolygonFillMode>,
+    polygon_offset: Cached<Option<PolygonOffset>>,
+    line_width: Cached<f32>,
+    depth_clamp: Cached<bool>,
+    depth_range: Cached<DepthRange>,
+    clip_depth: Cached<ClipDepth>,
+    primitive_restart: Cached<Option<u32>>,
+}
+
+impl TrackedState {
+    fn unknown(capabilities: &GlCapabilities) -> Self {
+        let draw_buffers = capabilities.max_draw_buffers.max(1) as usize;
+        Self {
+            texture_units: vec![Default::default(); capabilities.max_texture_units as usize],
+            blend_enabled: vec![Default::default(); draw_buffers],
+            blend_func: vec![Default::default(); draw_buffers],
+            ..Default::default()
+        }
+    }
+
+    // Default state of a fresh context.
+    fn initial(capabilities: &GlCapabilities, viewport: Rect<i32>) -> Self {
+        let draw_buffers = capabilities.max_draw_buffers.max(1) as usize;
+        Self {
+            vao: Cached::new(None),
+            vbo: Cached::new(None),
+            program: Cached::new(None),
+            texture_units: vec![
+                Cached::new(Default::default());
+                capabilities.max_texture_units as usize
+            ],
+            clear_color: Cached::new(Color::from_rgba(0, 0, 0, 0)),
+            clear_depth: Cached::new(1.0),
+            clear_stencil: Cached::new(0),
+            color_write: Cached::new(Default::default()),
+            depth_write: Cached::new(true),
+            stencil_mask: [Cached::new(0xFFFF_FFFF); 2],
+            viewport: Cached::new(viewport),
+            scissor: Cached::new(None),
+            culling: Cached::new(false),
+            cull_face: Cached::new(CullFace::Back),
+            depth_test: Cached::new(false),
+            depth_func: Cached::new(CompareFunc::Less),
+            stencil_test: Cached::new(false),
+            stencil_func: [Cached::new(Default::default()); 2],
+            stencil_op: [Cached::new(Default::default()); 2],
+            blend_enabled: vec![Cached::new(false); draw_buffers],
+            blend_func: vec![Cached::new(Default::default()); draw_buffers],
+            blend_color: Cached::new(Default::default()),
+            polygon_mode: Cached::new(PolygonFillMode::Fill),
+            polygon_offset: Cached::new(None),
+            line_width: Cached::new(1.0),
+            depth_clamp: Cached::new(false),
+            depth_range: Cached::new(Default::default()),
+            clip_depth: Cached::new(ClipDepth::NegativeOneToOne),
+            primitive_restart: Cached::new(None),
+        }
+    }
+}
+
 
-#[derive(Copy, Clone)]
+#[derive(Copy, Clone, PartialEq)]
 struct TextureUnit {
     target: u32,
     texture: Option<glow::Texture>,
@@ -138,3 +1446,120 @@ impl Default for TextureUnit {
         }
     }
 }
+
+unsafe fn query_viewport(gl: &glow::Context) -> Rect<i32> {
+    let mut viewport = [0; 4];
+    gl.get_parameter_i32_slice(glow::VIEWPORT, &mut viewport);
+    Rect::new(viewport[0], viewport[1], viewport[2], viewport[3])
+}
+
+// Maps texture target to the parameter that returns texture bound to it.
+#[cfg(not(target_arch = "wasm32"))]
+fn texture_binding_parameter(target: u32) -> Option<u32> {
+    match target {
+        glow::TEXTURE_1D => Some(glow::TEXTURE_BINDING_1D),
+        glow::TEXTURE_2D => Some(glow::TEXTURE_BINDING_2D),
+        glow::TEXTURE_3D => Some(glow::TEXTURE_BINDING_3D),
+        glow::TEXTURE_2D_ARRAY => Some(glow::TEXTURE_BINDING_2D_ARRAY),
+        glow::TEXTURE_CUBE_MAP => Some(glow::TEXTURE_BINDING_CUBE_MAP),
+        _ => None,
+    }
+}
+
+// Converts name of GL object returned by glGet* to a handle. Handles of our glow fork are
+// plain wrappers over non-zero names.
+#[cfg(not(target_arch = "wasm32"))]
+pub(crate) fn object_from_name<T>(name: i32, wrap: fn(NonZeroU32) -> T) -> Option<T> {
+    NonZeroU32::new(name as u32).map(wrap)
+}
+
+// Reverse of object_from_name for query objects, raw query functions take names.
+#[cfg(not(target_arch = "wasm32"))]
+pub(crate) fn query_name(query: glow::Query) -> u32 {
+    query.0.get()
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        geometry_buffer::DrawCallStatistics,
+        pipeline_state::{PipelineStatistics, StatisticsHistory},
+    };
+
+    fn frame(draw_calls: usize, triangles: usize, bytes_uploaded: usize) -> PipelineStatistics {
+        PipelineStatistics {
+            draw_calls,
+            triangles,
+            bytes_uploaded,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_register_draw_call() {
+        let mut statistics = PipelineStatistics::default();
+        statistics.register_draw_call(DrawCallStatistics {
+            triangles: 10,
+            instances: 1,
+        });
+        statistics.register_draw_call(DrawCallStatistics {
+            triangles: 30,
+            instances: 3,
+        });
+        assert_eq!(statistics.draw_calls, 2);
+        assert_eq!(statistics.triangles, 40);
+        assert_eq!(statistics.instances, 4);
+    }
+
+    #[test]
+    fn test_statistics_history_average_and_peak() {
+        let mut history = StatisticsHistory::new(4);
+        assert!(history.is_empty());
+        assert_eq!(history.average().draw_calls, 0);
+        assert_eq!(history.peak().draw_calls, 0);
+
+        history.push(frame(1, 100, 10));
+        history.push(frame(2, 400, 0));
+        history.push(frame(6, 50, 30));
+
+        let average = history.average();
+        assert_eq!(average.draw_calls, 3);
+        // Rounded down.
+        assert_eq!(average.triangles, 183);
+        assert_eq!(average.bytes_uploaded, 13);
+        assert_eq!(average.instances, 0);
+
+        // Every counter peaks independently.
+        let peak = history.peak();
+        assert_eq!(peak.draw_calls, 6);
+        assert_eq!(peak.triangles, 400);
+        assert_eq!(peak.bytes_uploaded, 30);
+
+        assert_eq!(history.last().unwrap().draw_calls, 6);
+    }
+
+    #[test]
+    fn test_statistics_history_capacity() {
+        let mut history = StatisticsHistory::new(2);
+        history.push(frame(10, 0, 0));
+        history.push(frame(2, 0, 0));
+        history.push(frame(4, 0, 0));
+
+        // The oldest frame is discarded.
+        assert_eq!(history.len(), 2);
+        assert_eq!(history.peak().draw_calls, 4);
+        assert_eq!(history.average().draw_calls, 3);
+        assert_eq!(
+            history.iter().map(|f| f.draw_calls).collect::<Vec<_>>(),
+            [2, 4]
+        );
+
+        history.set_capacity(1);
+        assert_eq!(history.len(), 1);
+        assert_eq!(history.last().unwrap().draw_calls, 4);
+
+        history.set_capacity(0);
+        history.push(frame(1, 0, 0));
+        assert!(history.is_empty());
+    }
+}
@@ -6,6 +6,7 @@ use std::{
     path::{Path, PathBuf},
 };
 use crate::{
+    capabilities::GlCapabilities,
     core::{
         algebra::{Vector2, Vector3, Vector4,Matrix2,Matrix3,Matrix4},
         arrayvec::ArrayVec,
@@ -38,6 +39,113 @@ impl ShaderDefinition {
 }
 
 
+/// A GLSL dialect the shaders are compiled for. It is chosen at runtime from the version
+/// reported by the context, so the same build works on desktop GL, GLES and WebGL2.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum ShaderTarget {
+    /// OpenGL 3.3 core profile, `#version 330 core`.
+    Gl33Core,
+    /// OpenGL 4.x core profile, `#version 4x0 core`.
+    Gl4 {
+        /// Minor version of the context.
+        minor: u32,
+    },
+    /// OpenGL ES 3.0 (and newer), `#version 300 es`.
+    Gles30,
+    /// WebGL 2.0, `#version 300 es` with WebGL-specific defines.
+    WebGl2,
+}
+
+impl Default for ShaderTarget {
+    fn default() -> Self {
+        Self::Gl33Core
+    }
+}
+
+impl ShaderTarget {
+    /// Selects a target for a context with given capabilities.
+    pub fn from_capabilities(capabilities: &GlCapabilities) -> Self {
+        if capabilities.is_webgl {
+            Self::WebGl2
+        } else if capabilities.is_gles() {
+            Self::Gles30
+        } else if capabilities.major >= 4 {
+            Self::Gl4 {
+                minor: capabilities.minor,
+            }
+        } else {
+            Self::Gl33Core
+        }
+    }
+
+    /// Selects a target from the `GL_VERSION` string of a context, for example
+    /// `4.6.0 NVIDIA 510.47`, `OpenGL ES 3.2 Mesa 21.2.6` or `WebGL 2.0 (OpenGL ES 3.0 Chromium)`.
+    /// Intended for offline tooling that has no context, use [`Self::from_capabilities`] at
+    /// runtime.
+    pub fn from_version_string(version: &str) -> Self {
+        if version.starts_with("WebGL") {
+            return Self::WebGl2;
+        }
+
+        if version.contains("OpenGL ES") {
+            return Self::Gles30;
+        }
+
+        let mut numbers = version
+            .split(|c: char| !c.is_ascii_digit())
+            .filter(|n| !n.is_empty())
+            .map(|n| n.parse::<u32>().unwrap_or_default());
+
+        match (numbers.next(), numbers.next()) {
+            (Some(major), Some(minor)) if major >= 4 => Self::Gl4 { minor },
+            _ => Self::Gl33Core,
+        }
+    }
+
+    /// Returns true if the target is one of embedded profiles (GLES or WebGL).
+    pub fn is_embedded(self) -> bool {
+        matches!(self, Self::Gles30 | Self::WebGl2)
+    }
+
+    /// Returns GLSL version number that is used in `#version` directive.
+    pub fn glsl_version(self) -> u32 {
+        match self {
+            Self::Gl33Core => 330,
+            Self::Gl4 { minor } => 400 + minor * 10,
+            Self::Gles30 | Self::WebGl2 => 300,
+        }
+    }
+
+    /// Returns a header that is put in the beginning of every shader: version directive,
+    /// default precision statements for embedded profiles and defines that allow shaders
+    /// to gate features by target (`TARGET_GLES`, `TARGET_WEBGL2`, `TARGET_GL4`).
+    pub fn header(self) -> String {
+        let mut header = if self.is_embedded() {
+            format!("#version {} es\n", self.glsl_version())
+        } else {
+            format!("#version {} core\n", self.glsl_version())
+        };
+
+        match self {
+            Self::Gl33Core => (),
+            Self::Gl4 { .. } => header += "#define TARGET_GL4 1\n",
+            Self::Gles30 | Self::WebGl2 => {
+                header += "#define TARGET_GLES 1\n";
+                if self == Self::WebGl2 {
+                    header += "#define TARGET_WEBGL2 1\n";
+                }
+                header += "precision highp float;\n";
+                header += "precision highp int;\n";
+                header += "precision lowp usampler2D;\n";
+                header += "precision lowp sampler3D;\n";
+            }
+        }
+
+        header
+    }
+}
+
+
 /// A render pass definition. See [`Shader`] docs for more info about render passes.
 #[derive(Default, Deserialize, Debug, PartialEq)]
 pub struct RenderPassDefinition {
@@ -203,3 +311,121 @@ impl Default for SamplerFallback {
         Self::White
     }
 }
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        capabilities::{GlCapabilities, GlProfile},
+        shader::ShaderTarget,
+    };
+
+    fn capabilities(version: &str, major: u32, minor: u32, profile: GlProfile) -> GlCapabilities {
+        GlCapabilities {
+            version: version.to_owned(),
+            major,
+            minor,
+            profile,
+            is_webgl: version.starts_with("WebGL"),
+            vendor: Default::default(),
+            renderer: Default::default(),
+            max_texture_size: 4096,
+            max_texture_units: 16,
+            max_anisotropy: 1.0,
+            max_uniform_block_size: 16384,
+            max_draw_buffers: 4,
+            max_vertex_attrib_stride: 2048,
+            extensions: Default::default(),
+        }
+    }
+
+    #[test]
+    fn test_target_from_version_string() {
+        for (version, target) in [
+            ("4.6.0 NVIDIA 510.47", ShaderTarget::Gl4 { minor: 6 }),
+            ("4.6.0", ShaderTarget::Gl4 { minor: 6 }),
+            ("4.1 ATI-4.5.14", ShaderTarget::Gl4 { minor: 1 }),
+            ("3.3.0 Mesa 21.2.6", ShaderTarget::Gl33Core),
+            ("OpenGL ES 3.0 Mesa 21.2.6", ShaderTarget::Gles30),
+            ("OpenGL ES 3.2 NVIDIA 510.47", ShaderTarget::Gles30),
+            ("WebGL 2.0 (OpenGL ES 3.0 Chromium)", ShaderTarget::WebGl2),
+            ("WebGL 2.0", ShaderTarget::WebGl2),
+            ("", ShaderTarget::Gl33Core),
+        ] {
+            assert_eq!(
+                ShaderTarget::from_version_string(version),
+                target,
+                "{}",
+                version
+            );
+        }
+    }
+
+    #[test]
+    fn test_target_from_capabilities() {
+        for (capabilities, target) in [
+            (
+                capabilities("4.6.0", 4, 6, GlProfile::Core),
+                ShaderTarget::Gl4 { minor: 6 },
+            ),
+            (
+                capabilities("4.3.0", 4, 3, GlProfile::Compatibility),
+                ShaderTarget::Gl4 { minor: 3 },
+            ),
+            (
+                capabilities("3.3.0", 3, 3, GlProfile::Core),
+                ShaderTarget::Gl33Core,
+            ),
+            (
+                capabilities("OpenGL ES 3.2", 3, 2, GlProfile::Es),
+                ShaderTarget::Gles30,
+            ),
+            (
+                capabilities("WebGL 2.0 (OpenGL ES 3.0 Chromium)", 3, 0, GlProfile::Es),
+                ShaderTarget::WebGl2,
+            ),
+        ] {
+            assert_eq!(
+                ShaderTarget::from_capabilities(&capabilities),
+                target,
+                "{}",
+                capabilities.version
+            );
+            // Both ways of selecting a target must agree.
+            assert_eq!(
+                ShaderTarget::from_version_string(&capabilities.version),
+                target
+            );
+        }
+    }
+
+    #[test]
+    fn test_target_header() {
+        for (target, version) in [
+            (ShaderTarget::Gl33Core, "#version 330 core\n"),
+            (ShaderTarget::Gl4 { minor: 5 }, "#version 450 core\n"),
+            (ShaderTarget::Gles30, "#version 300 es\n"),
+            (ShaderTarget::WebGl2, "#version 300 es\n"),
+        ] {
+            let header = target.header();
+            assert!(header.starts_with(version), "{:?}", target);
+            assert_eq!(
+                header.contains("precision "),
+                target.is_embedded(),
+                "{:?}",
+                target
+            );
+            assert_eq!(
+                header.contains("#define TARGET_GLES 1"),
+                target.is_embedded()
+            );
+            assert_eq!(
+                header.contains("#define TARGET_WEBGL2 1"),
+                target == ShaderTarget::WebGl2
+            );
+            assert_eq!(
+                header.contains("#define TARGET_GL4 1"),
+                matches!(target, ShaderTarget::Gl4 { .. })
+            );
+        }
+    }
+}
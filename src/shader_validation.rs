@@ -0,0 +1,249 @@
+//! Offline validation of shader definitions. It does not require a GL context, so broken
+//! shaders can be caught on machines without GPU (CI servers for example).
+
+use crate::gpu_program::prepare_source_code;
+use crate::shader::{ShaderDefinition, ShaderError, ShaderTarget};
+use glsl::{
+    parser::Parse,
+    syntax::{ExternalDeclaration, TranslationUnit},
+};
+use std::fmt::{Display, Formatter};
+use std::path::Path;
+
+/// A stage of a render pass which source code failed validation.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum ShaderStage {
+    /// Vertex shader of a pass.
+    Vertex,
+    /// Fragment shader of a pass.
+    Fragment,
+}
+
+impl Display for ShaderStage {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ShaderStage::Vertex => write!(f, "vertex"),
+            ShaderStage::Fragment => write!(f, "fragment"),
+        }
+    }
+}
+
+/// A location of an error in the source code.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum SourceLine {
+    /// Line of the source code of the pass, as it is written in the definition (1-based).
+    Pass(usize),
+    /// Line inside of the automatically included code (version header and `shared.glsl`),
+    /// 1-based.
+    Prelude(usize),
+    /// Parser did not report a line.
+    Unknown,
+}
+
+/// An error found in a single render pass of a shader definition.
+#[derive(Debug)]
+pub struct ShaderValidationError {
+    /// A name of the render pass.
+    pub pass: String,
+    /// A stage which source code is invalid.
+    pub stage: ShaderStage,
+    /// A place in the source code where the error was detected.
+    pub line: SourceLine,
+    /// Error message from the GLSL front end.
+    pub message: String,
+}
+
+impl Display for ShaderValidationError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self.line {
+            SourceLine::Pass(line) => write!(
+                f,
+                "{} shader of pass \"{}\", line {}: {}",
+                self.stage, self.pass, line, self.message
+            ),
+            SourceLine::Prelude(line) => write!(
+                f,
+                "{} shader of pass \"{}\", included code line {}: {}",
+                self.stage, self.pass, line, self.message
+            ),
+            SourceLine::Unknown => write!(
+                f,
+                "{} shader of pass \"{}\": {}",
+                self.stage, self.pass, self.message
+            ),
+        }
+    }
+}
+
+impl std::error::Error for ShaderValidationError {}
+
+/// Loads shader definition from a RON file (`.shader`) and validates every pass of it.
+/// Returns an error only if the file cannot be read or parsed, validation errors are
+/// returned in the vector (which is empty if all passes are valid).
+pub fn validate_shader_file<P: AsRef<Path>>(
+    path: P,
+    target: ShaderTarget,
+) -> Result<Vec<ShaderValidationError>, ShaderError> {
+    let content = std::fs::read(path.as_ref()).map_err(|e| ShaderError::Io(e.into()))?;
+    let definition = ShaderDefinition::from_buf(content)?;
+    Ok(validate_shader_definition(&definition, target))
+}
+
+/// Validates every pass of the shader definition. Source code of each stage is merged with
+/// `shared.glsl` for given target exactly as it is done at runtime before compilation, then
+/// parsed with a pure-Rust GLSL front end.
+pub fn validate_shader_definition(
+    definition: &ShaderDefinition,
+    target: ShaderTarget,
+) -> Vec<ShaderValidationError> {
+    let mut errors = Vec::new();
+
+    for pass in definition.passes.iter() {
+        for (stage, source) in [
+            (ShaderStage::Vertex, &pass.vertex_shader),
+            (ShaderStage::Fragment, &pass.fragment_shader),
+        ] {
+            if let Err((line, message)) = validate_source(source, target) {
+                errors.push(ShaderValidationError {
+                    pass: pass.name.clone(),
+                    stage,
+                    line,
+                    message,
+                });
+            }
+        }
+    }
+
+    errors
+}
+
+fn validate_source(source: &str, target: ShaderTarget) -> Result<(), (SourceLine, String)> {
+    let merged_source = prepare_source_code(source, target);
+
+    let unit = TranslationUnit::parse(&merged_source)
+        .map_err(|e| (map_line(parse_error_line(&e.info), target), e.info))?;
+
+    let has_entry_point = unit.0.into_iter().any(|declaration| {
+        matches!(declaration, ExternalDeclaration::FunctionDefinition(ref function)
+            if function.prototype.name.as_str() == "main")
+    });
+
+    if has_entry_point {
+        Ok(())
+    } else {
+        Err((
+            SourceLine::Unknown,
+            "Entry point (void main()) is not defined".to_owned(),
+        ))
+    }
+}
+
+// Amount of lines that prepare_source_code puts before actual source code of a shader.
+fn prelude_line_count(target: ShaderTarget) -> usize {
+    prepare_source_code("", target).lines().count()
+}
+
+// Maps line of the merged source code back to the line of the original source.
+fn map_line(merged_line: Option<usize>, target: ShaderTarget) -> SourceLine {
+    match merged_line {
+        Some(line) => {
+            let prelude = prelude_line_count(target);
+            if line > prelude {
+                SourceLine::Pass(line - prelude)
+            } else {
+                SourceLine::Prelude(line)
+            }
+        }
+        None => SourceLine::Unknown,
+    }
+}
+
+// Parser reports errors in form of "0: at line N:\n...", extract N from it.
+fn parse_error_line(info: &str) -> Option<usize> {
+    const PATTERN: &str = "at line ";
+    let start = info.find(PATTERN)? + PATTERN.len();
+    let digits = info[start..]
+        .chars()
+        .take_while(|c| c.is_ascii_digit())
+        .collect::<String>();
+    digits.parse().ok()
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        shader::{RenderPassDefinition, ShaderDefinition, ShaderTarget},
+        shader_validation::{validate_shader_definition, ShaderStage, SourceLine},
+    };
+
+    const VALID_VERTEX: &str = r#"
+layout(location = 0) in vec3 vertexPosition;
+uniform mat4 worldViewProjection;
+void main()
+{
+    gl_Position = worldViewProjection * vec4(vertexPosition, 1.0);
+}
+"#;
+
+    const VALID_FRAGMENT: &str = r#"
+out vec4 FragColor;
+void main()
+{
+    FragColor = vec4(1.0);
+}
+"#;
+
+    fn definition(vertex_shader: &str, fragment_shader: &str) -> ShaderDefinition {
+        ShaderDefinition {
+            name: "Test".to_owned(),
+            passes: vec![RenderPassDefinition {
+                name: "Forward".to_owned(),
+                draw_parameters: Default::default(),
+                vertex_shader: vertex_shader.to_owned(),
+                fragment_shader: fragment_shader.to_owned(),
+            }],
+            properties: Default::default(),
+        }
+    }
+
+    #[test]
+    fn test_valid_shader() {
+        for target in [
+            ShaderTarget::Gl33Core,
+            ShaderTarget::Gl4 { minor: 6 },
+            ShaderTarget::Gles30,
+            ShaderTarget::WebGl2,
+        ] {
+            let definition = definition(VALID_VERTEX, VALID_FRAGMENT);
+            let errors = validate_shader_definition(&definition, target);
+            assert!(errors.is_empty(), "{:?}: {:?}", target, errors);
+        }
+    }
+
+    #[test]
+    fn test_syntax_error_line() {
+        // The error is on the 5th line of the source, the first one is empty.
+        let fragment = "\nout vec4 FragColor;\nvoid main()\n{\n    FragColor = vec4(1.0)\n}\n";
+
+        let errors =
+            validate_shader_definition(&definition(VALID_VERTEX, fragment), ShaderTarget::Gl33Core);
+        assert_eq!(errors.len(), 1);
+
+        let error = &errors[0];
+        assert_eq!(error.pass, "Forward");
+        assert_eq!(error.stage, ShaderStage::Fragment);
+        // Line is counted from the beginning of the pass source, not the merged source.
+        assert_eq!(error.line, SourceLine::Pass(5));
+    }
+
+    #[test]
+    fn test_missing_main() {
+        let vertex = "layout(location = 0) in vec3 vertexPosition;\nvoid foo() {}\n";
+
+        let errors =
+            validate_shader_definition(&definition(vertex, VALID_FRAGMENT), ShaderTarget::Gl33Core);
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].stage, ShaderStage::Vertex);
+        assert!(errors[0].message.contains("main"));
+    }
+}
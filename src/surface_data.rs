@@ -1,4 +1,7 @@
 use crate::vertex_buffer::{VertexBuffer,VertexAttributeUsage,VertexFetchError};
+use crate::vertex_buffer::{
+    ValidationError, VertexAttribute, VertexAttributeDataType, VertexAttributeDescriptor,
+};
 use crate::vertex_buffer::VertexWriteTrait;
 use crate::vertex_buffer::VertexReadTrait;
 use crate::vertex::{StaticVertex};
@@ -12,6 +15,7 @@ use std::ops::Deref;
 use std::ops::DerefMut;
 use std::ops::Index;
 use std::ops::IndexMut;
+use std::io::{Read, Write};
 
 use crate::core::math::{TriangleDefinition};
 use fyrox::renderer::{cache::CacheEntry, framework};
@@ -288,9 +292,9 @@ impl SurfaceData{
             let v2 = view2.read_3_f32(VertexAttributeUsage::Position)?;
             let v3 = view3.read_3_f32(VertexAttributeUsage::Position)?;
 
-            let w1 = view1.read_3_f32(VertexAttributeUsage::TexCoord0)?;
-            let w2 = view2.read_3_f32(VertexAttributeUsage::TexCoord0)?;
-            let w3 = view3.read_3_f32(VertexAttributeUsage::TexCoord0)?;
+            let w1 = view1.read_2_f32(VertexAttributeUsage::TexCoord0)?;
+            let w2 = view2.read_2_f32(VertexAttributeUsage::TexCoord0)?;
+            let w3 = view3.read_2_f32(VertexAttributeUsage::TexCoord0)?;
 
             let x1 = v2.x - v1.x;
             let x2 = v3.x - v1.x;
@@ -378,6 +382,365 @@ impl SurfaceData{
 
 
 
+/// Magic bytes at the beginning of binary surface data.
+pub const SURFACE_DATA_MAGIC: [u8; 4] = *b"RSGS";
+
+/// Current version of binary surface data format.
+pub const SURFACE_DATA_VERSION: u32 = 1;
+
+// LZ4 cannot expand data more than ~255 times, larger claimed sizes are corrupted.
+#[cfg(feature = "lz4")]
+const LZ4_MAX_COMPRESSION_RATIO: u64 = 255;
+
+/// Compression of binary surface data payload.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum SurfaceDataCompression {
+    /// Payload is stored as is.
+    None,
+    /// LZ4 block compression, requires `lz4` feature.
+    Lz4,
+    /// Zstandard compression with given level, requires `zstd` feature.
+    Zstd(i32),
+}
+
+impl Default for SurfaceDataCompression {
+    fn default() -> Self {
+        Self::None
+    }
+}
+
+impl SurfaceDataCompression {
+    fn id(self) -> u8 {
+        match self {
+            Self::None => 0,
+            Self::Lz4 => 1,
+            Self::Zstd(_) => 2,
+        }
+    }
+}
+
+/// An error that may occur during saving or loading of binary surface data.
+#[derive(Debug, thiserror::Error)]
+pub enum SurfaceDataError {
+    /// IO error.
+    #[error("An i/o error has occurred {0:?}")]
+    Io(std::io::Error),
+    /// Data does not start with [`SURFACE_DATA_MAGIC`].
+    #[error("Data is not a surface data")]
+    InvalidMagic,
+    /// Data was written by a newer version of the format.
+    #[error("Unsupported surface data version {0}")]
+    UnsupportedVersion(u32),
+    /// Compression is unknown or its feature is disabled.
+    #[error("Unsupported surface data compression {0}")]
+    UnsupportedCompression(u8),
+    /// Compression or decompression has failed.
+    #[error("Compression error: {0}")]
+    Compression(String),
+    /// Data is truncated or has invalid values.
+    #[error("Surface data is corrupted: {0}")]
+    Corrupted(String),
+    /// Vertex layout or vertex data is invalid.
+    #[error("Invalid vertex buffer: {0}")]
+    Validation(ValidationError),
+}
+
+impl From<std::io::Error> for SurfaceDataError {
+    fn from(e: std::io::Error) -> Self {
+        Self::Io(e)
+    }
+}
+
+impl From<ValidationError> for SurfaceDataError {
+    fn from(e: ValidationError) -> Self {
+        Self::Validation(e)
+    }
+}
+
+impl SurfaceData {
+    /// Writes the surface data in uncompressed binary format, see
+    /// [`Self::save_with_compression`].
+    pub fn save<W: Write>(&self, writer: &mut W) -> Result<(), SurfaceDataError> {
+        self.save_with_compression(writer, SurfaceDataCompression::None)
+    }
+
+    /// Writes the surface data in binary format. All values are little-endian:
+    ///
+    /// ```text
+    /// magic               [u8; 4]  "RSGS"
+    /// version             u32      SURFACE_DATA_VERSION
+    /// compression         u8       0 - none, 1 - LZ4 block, 2 - Zstandard
+    /// payload size        u64      size of uncompressed payload
+    /// stored size         u64      size of payload as stored below
+    /// payload             [u8]     possibly compressed:
+    ///   is procedural     u8       0 or 1
+    ///   attribute count   u8
+    ///   attributes        [attribute; attribute count]:
+    ///     usage           u8       VertexAttributeUsage::index
+    ///     data type       u8       VertexAttributeDataType as u8
+    ///     size            u8
+    ///     divisor         u8
+    ///     shader location u8
+    ///     flags           u8       bit 0 - normalized, bit 1 - integer
+    ///   vertex count      u32
+    ///   vertex size       u16
+    ///   vertex data       [u8; vertex count * vertex size], components are little-endian
+    ///   triangle count    u32
+    ///   indices           [u32; triangle count * 3]
+    /// ```
+    pub fn save_with_compression<W: Write>(
+        &self,
+        writer: &mut W,
+        compression: SurfaceDataCompression,
+    ) -> Result<(), SurfaceDataError> {
+        let payload = self.write_payload()?;
+
+        let stored = match compression {
+            SurfaceDataCompression::None => payload.clone(),
+            #[cfg(feature = "lz4")]
+            SurfaceDataCompression::Lz4 => lz4_flex::block::compress(&payload),
+            #[cfg(feature = "zstd")]
+            SurfaceDataCompression::Zstd(level) => zstd::bulk::compress(&payload, level)
+                .map_err(|e| SurfaceDataError::Compression(e.to_string()))?,
+            #[allow(unreachable_patterns)]
+            _ => return Err(SurfaceDataError::UnsupportedCompression(compression.id())),
+        };
+
+        writer.write_all(&SURFACE_DATA_MAGIC)?;
+        writer.write_u32::<LittleEndian>(SURFACE_DATA_VERSION)?;
+        writer.write_u8(compression.id())?;
+        writer.write_u64::<LittleEndian>(payload.len() as u64)?;
+        writer.write_u64::<LittleEndian>(stored.len() as u64)?;
+        writer.write_all(&stored)?;
+
+        Ok(())
+    }
+
+    /// Reads surface data written by [`Self::save`] or [`Self::save_with_compression`].
+    pub fn load<R: Read>(reader: &mut R) -> Result<Self, SurfaceDataError> {
+        let mut magic = [0; 4];
+        reader.read_exact(&mut magic)?;
+        if magic != SURFACE_DATA_MAGIC {
+            return Err(SurfaceDataError::InvalidMagic);
+        }
+
+        let version = reader.read_u32::<LittleEndian>()?;
+        if version == 0 || version > SURFACE_DATA_VERSION {
+            return Err(SurfaceDataError::UnsupportedVersion(version));
+        }
+
+        let compression = reader.read_u8()?;
+        let payload_size = reader.read_u64::<LittleEndian>()?;
+        let stored_size = reader.read_u64::<LittleEndian>()?;
+
+        // Do not trust sizes blindly, read no more than the stream actually has.
+        let mut stored = Vec::new();
+        reader.take(stored_size).read_to_end(&mut stored)?;
+        if stored.len() as u64 != stored_size {
+            return Err(SurfaceDataError::Corrupted("payload is truncated".to_string()));
+        }
+
+        let payload = match compression {
+            0 => stored,
+            #[cfg(feature = "lz4")]
+            1 => {
+                // LZ4 block decompression allocates the whole output upfront, so the size
+                // must be bounded by what the stored data could possibly expand to.
+                if payload_size > stored_size.saturating_mul(LZ4_MAX_COMPRESSION_RATIO) {
+                    return Err(SurfaceDataError::Corrupted(format!(
+                        "payload size {} is too large for {} compressed bytes",
+                        payload_size, stored_size
+                    )));
+                }
+                let payload_size = usize::try_from(payload_size).map_err(|_| {
+                    SurfaceDataError::Corrupted(format!(
+                        "payload size {} is too large",
+                        payload_size
+                    ))
+                })?;
+                lz4_flex::block::decompress(&stored, payload_size)
+                    .map_err(|e| SurfaceDataError::Compression(e.to_string()))?
+            }
+            #[cfg(feature = "zstd")]
+            2 => {
+                // Streaming decoder grows the output as it goes, read one byte more than
+                // expected to detect oversized payloads without decoding them completely.
+                let mut payload = Vec::new();
+                zstd::stream::read::Decoder::new(stored.as_slice())
+                    .and_then(|decoder| {
+                        decoder
+                            .take(payload_size.saturating_add(1))
+                            .read_to_end(&mut payload)
+                    })
+                    .map_err(|e| SurfaceDataError::Compression(e.to_string()))?;
+                payload
+            }
+            _ => return Err(SurfaceDataError::UnsupportedCompression(compression)),
+        };
+
+        if payload.len() as u64 != payload_size {
+            return Err(SurfaceDataError::Corrupted(format!(
+                "payload size mismatch, expected {}, got {}",
+                payload_size,
+                payload.len()
+            )));
+        }
+
+        Self::read_payload(&payload)
+    }
+
+    fn write_payload(&self) -> Result<Vec<u8>, SurfaceDataError> {
+        let layout = self.vertex_buffer.layout();
+        let vertex_size = self.vertex_buffer.vertex_size_in_byte();
+
+        let mut payload = Vec::new();
+        payload.write_u8(self.is_procedural as u8)?;
+        payload.write_u8(layout.len() as u8)?;
+        for attribute in layout {
+            payload.write_u8(attribute.usage.index() as u8)?;
+            payload.write_u8(attribute.data_type as u8)?;
+            payload.write_u8(attribute.size)?;
+            payload.write_u8(attribute.divisor)?;
+            payload.write_u8(attribute.shader_location)?;
+            payload.write_u8(attribute.normalized as u8 | (attribute.integer as u8) << 1)?;
+        }
+
+        payload.write_u32::<LittleEndian>(self.vertex_buffer.vertex_count())?;
+        payload.write_u16::<LittleEndian>(vertex_size)?;
+        let vertex_data_offset = payload.len();
+        payload.extend_from_slice(self.vertex_buffer.raw_data());
+        swap_vertex_bytes(&mut payload[vertex_data_offset..], layout, vertex_size);
+
+        payload.write_u32::<LittleEndian>(self.geometry_buffer.len() as u32)?;
+        for triangle in self.geometry_buffer.iter() {
+            for &index in triangle.0.iter() {
+                payload.write_u32::<LittleEndian>(index)?;
+            }
+        }
+
+        Ok(payload)
+    }
+
+    fn read_payload(mut payload: &[u8]) -> Result<Self, SurfaceDataError> {
+        let is_procedural = payload.read_u8()? != 0;
+
+        let attribute_count = payload.read_u8()?;
+        let mut layout = Vec::with_capacity(attribute_count as usize);
+        for _ in 0..attribute_count {
+            let usage = payload.read_u8()?;
+            let data_type = payload.read_u8()?;
+            let size = payload.read_u8()?;
+            let divisor = payload.read_u8()?;
+            let shader_location = payload.read_u8()?;
+            let flags = payload.read_u8()?;
+            layout.push(VertexAttributeDescriptor {
+                usage: VertexAttributeUsage::from_index(usage as usize).ok_or_else(|| {
+                    SurfaceDataError::Corrupted(format!("unknown attribute usage {}", usage))
+                })?,
+                data_type: VertexAttributeDataType::from_u8(data_type).ok_or_else(|| {
+                    SurfaceDataError::Corrupted(format!("unknown data type {}", data_type))
+                })?,
+                size,
+                divisor,
+                shader_location,
+                normalized: flags & 1 != 0,
+                integer: flags & 2 != 0,
+            });
+        }
+
+        let vertex_count = payload.read_u32::<LittleEndian>()? as usize;
+        let vertex_size = payload.read_u16::<LittleEndian>()?;
+
+        // Validate the layout first, it defines the vertex size.
+        let empty = VertexBuffer::new(0, &layout, Vec::<u8>::new())?;
+        if empty.vertex_size_in_byte() != vertex_size {
+            return Err(SurfaceDataError::Corrupted(format!(
+                "vertex size mismatch, layout defines {}, stored {}",
+                empty.vertex_size_in_byte(),
+                vertex_size
+            )));
+        }
+
+        let vertex_data_size = match vertex_count.checked_mul(vertex_size as usize) {
+            Some(size) if size <= payload.len() => size,
+            _ => {
+                return Err(SurfaceDataError::Corrupted(
+                    "vertex data is truncated".to_string(),
+                ))
+            }
+        };
+        let mut vertex_data = payload[..vertex_data_size].to_vec();
+        payload = &payload[vertex_data_size..];
+        swap_vertex_bytes(&mut vertex_data, empty.layout(), vertex_size);
+        let vertex_buffer = VertexBuffer::new(vertex_count, &layout, vertex_data)?;
+
+        let triangle_count = payload.read_u32::<LittleEndian>()? as usize;
+        let index_data_size = triangle_count
+            .checked_mul(3 * std::mem::size_of::<u32>())
+            .ok_or_else(|| SurfaceDataError::Corrupted("indices are truncated".to_string()))?;
+        if payload.len() < index_data_size {
+            return Err(SurfaceDataError::Corrupted(
+                "indices are truncated".to_string(),
+            ));
+        }
+        let mut triangles = Vec::with_capacity(triangle_count);
+        for _ in 0..triangle_count {
+            let mut triangle = TriangleDefinition([0; 3]);
+            for index in triangle.0.iter_mut() {
+                *index = payload.read_u32::<LittleEndian>()?;
+                if *index as usize >= vertex_count {
+                    return Err(SurfaceDataError::Corrupted(format!(
+                        "index {} is out of bounds, vertex count is {}",
+                        index, vertex_count
+                    )));
+                }
+            }
+            triangles.push(triangle);
+        }
+
+        if !payload.is_empty() {
+            return Err(SurfaceDataError::Corrupted(format!(
+                "{} unexpected bytes after indices",
+                payload.len()
+            )));
+        }
+
+        Ok(Self::new(
+            vertex_buffer,
+            TriangleBuffer::new(triangles),
+            is_procedural,
+        ))
+    }
+}
+
+// Vertex data is stored in native byte order in memory, but in little-endian in binary format,
+// so components have to be swapped on big-endian machines. Swapping is symmetric, so the same
+// function is used for both saving and loading.
+#[cfg(target_endian = "big")]
+fn swap_vertex_bytes(data: &mut [u8], layout: &[VertexAttribute], vertex_size: u16) {
+    if vertex_size == 0 {
+        return;
+    }
+
+    for vertex in data.chunks_exact_mut(vertex_size as usize) {
+        for attribute in layout {
+            let component_size = if attribute.data_type.is_packed() {
+                4
+            } else {
+                attribute.data_type.size() as usize
+            };
+            let offset = attribute.offset as usize;
+            let size = attribute.data_type.attribute_size(attribute.size) as usize;
+            for component in vertex[offset..(offset + size)].chunks_exact_mut(component_size) {
+                component.reverse();
+            }
+        }
+    }
+}
+
+#[cfg(target_endian = "little")]
+fn swap_vertex_bytes(_data: &mut [u8], _layout: &[VertexAttribute], _vertex_size: u16) {}
+
 /// A buffer for data that defines connections between vertices.
 #[derive(Visit, Default, Clone, Debug)]
 pub struct TriangleBuffer {
@@ -499,3 +862,186 @@ impl<'a> IndexMut<usize> for TriangleBufferRefMut<'a> {
         &mut self.triangle_buffer.triangles[index]
     }
 }
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        core::algebra::Matrix4,
+        surface_data::{SurfaceData, SurfaceDataCompression, SurfaceDataError},
+    };
+
+    // Magic, version and compression id precede the payload size, the stored size follows it.
+    #[cfg(any(feature = "lz4", feature = "zstd"))]
+    const PAYLOAD_SIZE_OFFSET: usize = 9;
+    #[cfg(any(feature = "lz4", feature = "zstd"))]
+    const STORED_SIZE_OFFSET: usize = 17;
+
+    fn save(data: &SurfaceData, compression: SurfaceDataCompression) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        data.save_with_compression(&mut bytes, compression).unwrap();
+        bytes
+    }
+
+    fn cube_bytes() -> (SurfaceData, Vec<u8>) {
+        let data = SurfaceData::make_cube(Matrix4::identity());
+        let bytes = save(&data, SurfaceDataCompression::None);
+        (data, bytes)
+    }
+
+    #[cfg(any(feature = "lz4", feature = "zstd"))]
+    fn read_size(bytes: &[u8], offset: usize) -> u64 {
+        u64::from_le_bytes(bytes[offset..offset + 8].try_into().unwrap())
+    }
+
+    #[cfg(any(feature = "lz4", feature = "zstd"))]
+    fn write_size(bytes: &mut [u8], offset: usize, size: u64) {
+        bytes[offset..offset + 8].copy_from_slice(&size.to_le_bytes());
+    }
+
+    fn assert_same(expected: &SurfaceData, actual: &SurfaceData) {
+        let expected_layout = expected.vertex_buffer.layout();
+        let actual_layout = actual.vertex_buffer.layout();
+        assert_eq!(expected_layout.len(), actual_layout.len());
+        for (expected, actual) in expected_layout.iter().zip(actual_layout) {
+            assert_eq!(expected.usage, actual.usage);
+            assert_eq!(expected.data_type, actual.data_type);
+            assert_eq!(expected.size, actual.size);
+            assert_eq!(expected.divisor, actual.divisor);
+            assert_eq!(expected.offset, actual.offset);
+            assert_eq!(expected.shader_location, actual.shader_location);
+            assert_eq!(expected.normalized, actual.normalized);
+            assert_eq!(expected.integer, actual.integer);
+        }
+
+        assert_eq!(
+            expected.vertex_buffer.vertex_count(),
+            actual.vertex_buffer.vertex_count()
+        );
+        assert_eq!(
+            expected.vertex_buffer.raw_data(),
+            actual.vertex_buffer.raw_data()
+        );
+        assert_eq!(
+            expected.geometry_buffer.triangles_ref(),
+            actual.geometry_buffer.triangles_ref()
+        );
+    }
+
+    #[test]
+    fn test_surface_data_round_trip() {
+        let (data, bytes) = cube_bytes();
+        let loaded = SurfaceData::load(&mut bytes.as_slice()).unwrap();
+        assert_same(&data, &loaded);
+    }
+
+    #[test]
+    fn test_surface_data_truncated() {
+        let (_, bytes) = cube_bytes();
+        for len in [0, 3, 8, 20, bytes.len() / 2, bytes.len() - 1] {
+            assert!(SurfaceData::load(&mut &bytes[..len]).is_err());
+        }
+    }
+
+    #[test]
+    fn test_surface_data_invalid_magic() {
+        let (_, mut bytes) = cube_bytes();
+        bytes[0] = b'X';
+        assert!(matches!(
+            SurfaceData::load(&mut bytes.as_slice()),
+            Err(SurfaceDataError::InvalidMagic)
+        ));
+    }
+
+    #[cfg(feature = "lz4")]
+    #[test]
+    fn test_surface_data_lz4_round_trip() {
+        let data = SurfaceData::make_cube(Matrix4::identity());
+        let bytes = save(&data, SurfaceDataCompression::Lz4);
+        assert!(read_size(&bytes, STORED_SIZE_OFFSET) < read_size(&bytes, PAYLOAD_SIZE_OFFSET));
+
+        let loaded = SurfaceData::load(&mut bytes.as_slice()).unwrap();
+        assert_same(&data, &loaded);
+    }
+
+    #[cfg(feature = "lz4")]
+    #[test]
+    fn test_surface_data_lz4_corrupted_payload_size() {
+        let data = SurfaceData::make_cube(Matrix4::identity());
+        let bytes = save(&data, SurfaceDataCompression::Lz4);
+        let payload_size = read_size(&bytes, PAYLOAD_SIZE_OFFSET);
+        let stored_size = read_size(&bytes, STORED_SIZE_OFFSET);
+
+        // Sizes that LZ4 cannot possibly produce are rejected before decompression.
+        for size in [stored_size * 255 + 1, u64::MAX] {
+            let mut bytes = bytes.clone();
+            write_size(&mut bytes, PAYLOAD_SIZE_OFFSET, size);
+            match SurfaceData::load(&mut bytes.as_slice()) {
+                Err(SurfaceDataError::Corrupted(message)) => {
+                    assert!(message.contains("too large"), "{}", message)
+                }
+                other => panic!("{} must be rejected, got {:?}", size, other.err()),
+            }
+        }
+
+        // Plausible, but wrong sizes fail in decompression.
+        for size in [payload_size - 1, payload_size + 1] {
+            let mut bytes = bytes.clone();
+            write_size(&mut bytes, PAYLOAD_SIZE_OFFSET, size);
+            assert!(SurfaceData::load(&mut bytes.as_slice()).is_err());
+        }
+    }
+
+    #[cfg(not(feature = "lz4"))]
+    #[test]
+    fn test_surface_data_lz4_unsupported() {
+        let data = SurfaceData::make_cube(Matrix4::identity());
+        assert!(matches!(
+            data.save_with_compression(&mut Vec::new(), SurfaceDataCompression::Lz4),
+            Err(SurfaceDataError::UnsupportedCompression(1))
+        ));
+    }
+
+    #[cfg(feature = "zstd")]
+    #[test]
+    fn test_surface_data_zstd_round_trip() {
+        let data = SurfaceData::make_cube(Matrix4::identity());
+        for level in [1, 3, 19] {
+            let bytes = save(&data, SurfaceDataCompression::Zstd(level));
+            assert!(read_size(&bytes, STORED_SIZE_OFFSET) < read_size(&bytes, PAYLOAD_SIZE_OFFSET));
+
+            let loaded = SurfaceData::load(&mut bytes.as_slice()).unwrap();
+            assert_same(&data, &loaded);
+        }
+    }
+
+    #[cfg(feature = "zstd")]
+    #[test]
+    fn test_surface_data_zstd_corrupted_payload_size() {
+        let data = SurfaceData::make_cube(Matrix4::identity());
+        let bytes = save(&data, SurfaceDataCompression::Zstd(3));
+        let payload_size = read_size(&bytes, PAYLOAD_SIZE_OFFSET);
+
+        for size in [0, payload_size - 1, payload_size + 1, u64::MAX] {
+            let mut bytes = bytes.clone();
+            write_size(&mut bytes, PAYLOAD_SIZE_OFFSET, size);
+            assert!(
+                matches!(
+                    SurfaceData::load(&mut bytes.as_slice()),
+                    Err(SurfaceDataError::Corrupted(_))
+                ),
+                "{}",
+                size
+            );
+        }
+    }
+
+    #[cfg(not(feature = "zstd"))]
+    #[test]
+    fn test_surface_data_zstd_unsupported() {
+        let data = SurfaceData::make_cube(Matrix4::identity());
+        assert!(matches!(
+            data.save_with_compression(&mut Vec::new(), SurfaceDataCompression::Zstd(3)),
+            Err(SurfaceDataError::UnsupportedCompression(2))
+        ));
+    }
+}
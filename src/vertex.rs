@@ -2,27 +2,145 @@
 use crate::{
     core::{
         algebra::{Vector2, Vector3, Vector4,Matrix4},
+        color::Color,
     },
 };
 
-use crate::vertex_buffer::{VertexAttributeDescriptor, VertexAttributeUsage, VertexAttributeDataType};
+use crate::vertex_buffer::{VertexAttributeDescriptor, VertexAttributeDataType};
+
+pub use rs_gl_derive::VertexLayout;
+
+/// A vertex type with a known layout of attributes. Use `#[derive(VertexLayout)]` to implement
+/// it, so the layout can't drift out of sync with the structure.
+///
+/// ```
+/// use rs_gl::{core::algebra::Vector3, vertex::VertexLayout};
+///
+/// #[derive(Copy, Clone, VertexLayout)]
+/// #[repr(C)]
+/// struct Vertex {
+///     #[vertex(usage = "Position")]
+///     position: Vector3<f32>,
+///     #[vertex(usage = "Color", normalized)]
+///     color: [u8; 4],
+/// }
+///
+/// assert_eq!(Vertex::layout().len(), 2);
+/// ```
+///
+/// The structure must be `#[repr(C)]`, otherwise the compiler is free to reorder its fields:
+///
+/// ```compile_fail
+/// use rs_gl::{core::algebra::Vector3, vertex::VertexLayout};
+///
+/// #[derive(Copy, Clone, VertexLayout)]
+/// struct Vertex {
+///     #[vertex(usage = "Position")]
+///     position: Vector3<f32>,
+/// }
+/// ```
+///
+/// Fields must be tightly packed, padding between fields is rejected at compile time:
+///
+/// ```compile_fail
+/// use rs_gl::{core::algebra::Vector3, vertex::VertexLayout};
+///
+/// #[derive(Copy, Clone, VertexLayout)]
+/// #[repr(C)]
+/// struct Vertex {
+///     #[vertex(usage = "Custom(0)")]
+///     flags: u8,
+///     #[vertex(usage = "Position")]
+///     position: Vector3<f32>,
+/// }
+/// ```
+pub trait VertexLayout: Copy {
+    /// Returns layout of the vertex.
+    fn layout() -> &'static [VertexAttributeDescriptor];
+}
+
+/// A type of a vertex field that maps to a vertex attribute.
+pub trait VertexAttributeType {
+    /// Data type of every component of the attribute.
+    const DATA_TYPE: VertexAttributeDataType;
+    /// Amount of components of the attribute.
+    const SIZE: u8;
+}
+
+macro_rules! impl_vertex_attribute_type {
+    ($($ty:ty => $data_type:ident),*) => {
+        $(
+            impl VertexAttributeType for $ty {
+                const DATA_TYPE: VertexAttributeDataType = VertexAttributeDataType::$data_type;
+                const SIZE: u8 = 1;
+            }
+        )*
+    };
+}
+
+impl_vertex_attribute_type!(
+    f32 => F32,
+    u32 => U32,
+    u16 => U16,
+    u8 => U8,
+    i32 => I32,
+    i16 => I16,
+    i8 => I8
+);
+
+impl<T: VertexAttributeType> VertexAttributeType for Vector2<T> {
+    const DATA_TYPE: VertexAttributeDataType = T::DATA_TYPE;
+    const SIZE: u8 = 2;
+}
+
+impl<T: VertexAttributeType> VertexAttributeType for Vector3<T> {
+    const DATA_TYPE: VertexAttributeDataType = T::DATA_TYPE;
+    const SIZE: u8 = 3;
+}
+
+impl<T: VertexAttributeType> VertexAttributeType for Vector4<T> {
+    const DATA_TYPE: VertexAttributeDataType = T::DATA_TYPE;
+    const SIZE: u8 = 4;
+}
+
+impl<T: VertexAttributeType, const N: usize> VertexAttributeType for [T; N] {
+    const DATA_TYPE: VertexAttributeDataType = T::DATA_TYPE;
+    const SIZE: u8 = {
+        assert!(N >= 1 && N <= 4, "vertex attribute must have 1 to 4 components");
+        N as u8
+    };
+}
+
+impl VertexAttributeType for Color {
+    const DATA_TYPE: VertexAttributeDataType = VertexAttributeDataType::U8;
+    const SIZE: u8 = 4;
+}
 
 /// A vertex for static meshes.
-#[derive(Copy, Clone, Debug, Default)]
+#[derive(Copy, Clone, Debug, Default, VertexLayout)]
 #[repr(C)] // OpenGL expects this structure packed as in C
 pub struct StaticVertex {
     /// Position of vertex in local coordinates.
+    #[vertex(usage = "Position", location = 0)]
     pub position: Vector3<f32>,
     /// Texture coordinates.
+    #[vertex(usage = "TexCoord0", location = 1)]
     pub tex_coord: Vector2<f32>,
     /// Normal in local coordinates.
+    #[vertex(usage = "Normal", location = 2)]
     pub normal: Vector3<f32>,
     /// Tangent vector in local coordinates.
+    #[vertex(usage = "Tangent", location = 3)]
     pub tangent: Vector4<f32>,
 }
 
 
 impl StaticVertex {
+    /// Returns layout of the vertex, same as [`VertexLayout::layout`].
+    pub fn layout() -> &'static [VertexAttributeDescriptor] {
+        <Self as VertexLayout>::layout()
+    }
+
     /// Creates new vertex from given position and texture coordinates.
     pub fn from_pos_uv(position: Vector3<f32>, tex_coord: Vector2<f32>) -> Self {
         Self {
@@ -46,39 +164,295 @@ impl StaticVertex {
             tangent: Vector4::default(),
         }
     }
+}
 
-    /// Returns layout of the vertex.
-    pub fn layout() -> &'static [VertexAttributeDescriptor] {
-        static LAYOUT: [VertexAttributeDescriptor; 4] = [
-            VertexAttributeDescriptor {
-                usage: VertexAttributeUsage::Position,
-                data_type: VertexAttributeDataType::F32,
-                size: 3,
-                divisor: 0,
-                shader_location: 0,
-            },
-            VertexAttributeDescriptor {
-                usage: VertexAttributeUsage::TexCoord0,
-                data_type: VertexAttributeDataType::F32,
-                size: 2,
-                divisor: 0,
-                shader_location: 1,
+/// A vertex for skinned meshes.
+#[derive(Copy, Clone, Debug, Default, VertexLayout)]
+#[repr(C)] // OpenGL expects this structure packed as in C
+pub struct AnimatedVertex {
+    /// Position of vertex in local coordinates.
+    #[vertex(usage = "Position", location = 0)]
+    pub position: Vector3<f32>,
+    /// Texture coordinates.
+    #[vertex(usage = "TexCoord0", location = 1)]
+    pub tex_coord: Vector2<f32>,
+    /// Normal in local coordinates.
+    #[vertex(usage = "Normal", location = 2)]
+    pub normal: Vector3<f32>,
+    /// Tangent vector in local coordinates.
+    #[vertex(usage = "Tangent", location = 3)]
+    pub tangent: Vector4<f32>,
+    /// Array of bone weights. Unused bones will have 0.0 weight so they won't
+    /// impact the shape of mesh.
+    #[vertex(usage = "BoneWeight", location = 4)]
+    pub bone_weights: [f32; 4],
+    /// Array of bone indices. It has indices of bones in array of bones of a
+    /// surface.
+    #[vertex(usage = "BoneIndices", location = 5, integer)]
+    pub bone_indices: [u8; 4],
+}
+
+impl AnimatedVertex {
+    /// Creates new vertex from given position and texture coordinates, the vertex is not
+    /// affected by any bone.
+    pub fn from_pos_uv(position: Vector3<f32>, tex_coord: Vector2<f32>) -> Self {
+        Self {
+            position,
+            tex_coord,
+            normal: Vector3::new(0.0, 1.0, 0.0),
+            ..Default::default()
+        }
+    }
+
+    /// Creates new vertex from given position, texture coordinates and normal, the vertex is
+    /// not affected by any bone.
+    pub fn from_pos_uv_normal(
+        position: Vector3<f32>,
+        tex_coord: Vector2<f32>,
+        normal: Vector3<f32>,
+    ) -> Self {
+        Self {
+            position,
+            tex_coord,
+            normal,
+            ..Default::default()
+        }
+    }
+
+    /// Creates new skinned vertex from given static vertex and bone influences.
+    pub fn from_static(
+        vertex: StaticVertex,
+        bone_weights: [f32; 4],
+        bone_indices: [u8; 4],
+    ) -> Self {
+        Self {
+            position: vertex.position,
+            tex_coord: vertex.tex_coord,
+            normal: vertex.normal,
+            tangent: vertex.tangent,
+            bone_weights,
+            bone_indices,
+        }
+    }
+}
+
+/// A vertex that has only position, useful for depth and shadow passes.
+#[derive(Copy, Clone, Debug, Default, VertexLayout)]
+#[repr(C)] // OpenGL expects this structure packed as in C
+pub struct SimpleVertex {
+    /// Position of vertex in local coordinates.
+    #[vertex(usage = "Position", location = 0)]
+    pub position: Vector3<f32>,
+}
+
+impl SimpleVertex {
+    /// Creates new vertex from given position.
+    pub fn new(x: f32, y: f32, z: f32) -> Self {
+        Self {
+            position: Vector3::new(x, y, z),
+        }
+    }
+
+    /// Creates new vertex from given position.
+    pub fn from_pos(position: Vector3<f32>) -> Self {
+        Self { position }
+    }
+}
+
+/// A vertex with position and color, useful for debug drawing and vertex-painted geometry.
+#[derive(Copy, Clone, Debug, Default, VertexLayout)]
+#[repr(C)] // OpenGL expects this structure packed as in C
+pub struct ColoredVertex {
+    /// Position of vertex in local coordinates.
+    #[vertex(usage = "Position", location = 0)]
+    pub position: Vector3<f32>,
+    /// Color of vertex, read as normalized `vec4` in shader.
+    #[vertex(usage = "Color", location = 1, normalized)]
+    pub color: Color,
+}
+
+impl ColoredVertex {
+    /// Creates new vertex from given position and color.
+    pub fn from_pos_color(position: Vector3<f32>, color: Color) -> Self {
+        Self { position, color }
+    }
+}
+
+/// A vertex for 2D geometry, such as user interface.
+#[derive(Copy, Clone, Debug, Default, VertexLayout)]
+#[repr(C)] // OpenGL expects this structure packed as in C
+pub struct Vertex2D {
+    /// Position of vertex in screen or local coordinates.
+    #[vertex(usage = "Position", location = 0)]
+    pub position: Vector2<f32>,
+    /// Texture coordinates.
+    #[vertex(usage = "TexCoord0", location = 1)]
+    pub tex_coord: Vector2<f32>,
+    /// Color of vertex, read as normalized `vec4` in shader.
+    #[vertex(usage = "Color", location = 2, normalized)]
+    pub color: Color,
+}
+
+impl Vertex2D {
+    /// Creates new vertex from given position and texture coordinates, color of the vertex
+    /// is white.
+    pub fn from_pos_uv(position: Vector2<f32>, tex_coord: Vector2<f32>) -> Self {
+        Self {
+            position,
+            tex_coord,
+            color: Color::WHITE,
+        }
+    }
+
+    /// Creates new vertex from given position, texture coordinates and color.
+    pub fn from_pos_uv_color(
+        position: Vector2<f32>,
+        tex_coord: Vector2<f32>,
+        color: Color,
+    ) -> Self {
+        Self {
+            position,
+            tex_coord,
+            color,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        vertex::{
+            AnimatedVertex, ColoredVertex, SimpleVertex, StaticVertex, Vertex2D, VertexLayout,
+        },
+        vertex_buffer::{
+            VertexAttributeDataType as DataType, VertexAttributeUsage as Usage, VertexBuffer,
+        },
+    };
+    use std::mem::{offset_of, size_of};
+
+    struct Expected {
+        usage: Usage,
+        data_type: DataType,
+        size: u8,
+        offset: usize,
+        normalized: bool,
+        integer: bool,
+    }
+
+    fn attribute(usage: Usage, data_type: DataType, size: u8, offset: usize) -> Expected {
+        Expected {
+            usage,
+            data_type,
+            size,
+            offset,
+            normalized: false,
+            integer: false,
+        }
+    }
+
+    // Checks the layout through a vertex buffer, so offsets are the ones the buffer computes
+    // from the layout.
+    fn check_layout<T: VertexLayout>(expected: &[Expected]) {
+        let buffer = VertexBuffer::new(0, T::layout(), Vec::<T>::new()).unwrap();
+        assert_eq!(buffer.vertex_size_in_byte() as usize, size_of::<T>());
+        assert_eq!(buffer.layout().len(), expected.len());
+
+        for (location, (attribute, expected)) in buffer.layout().iter().zip(expected).enumerate() {
+            assert_eq!(attribute.usage, expected.usage);
+            assert_eq!(
+                attribute.data_type, expected.data_type,
+                "{:?}",
+                expected.usage
+            );
+            assert_eq!(attribute.size, expected.size, "{:?}", expected.usage);
+            assert_eq!(
+                attribute.offset as usize, expected.offset,
+                "{:?}",
+                expected.usage
+            );
+            assert_eq!(
+                attribute.normalized, expected.normalized,
+                "{:?}",
+                expected.usage
+            );
+            assert_eq!(attribute.integer, expected.integer, "{:?}", expected.usage);
+            assert_eq!(attribute.shader_location as usize, location);
+            assert_eq!(attribute.divisor, 0);
+        }
+    }
+
+    #[test]
+    fn test_static_vertex_layout() {
+        check_layout::<StaticVertex>(&[
+            attribute(
+                Usage::Position,
+                DataType::F32,
+                3,
+                offset_of!(StaticVertex, position),
+            ),
+            attribute(
+                Usage::TexCoord0,
+                DataType::F32,
+                2,
+                offset_of!(StaticVertex, tex_coord),
+            ),
+            attribute(
+                Usage::Normal,
+                DataType::F32,
+                3,
+                offset_of!(StaticVertex, normal),
+            ),
+            attribute(
+                Usage::Tangent,
+                DataType::F32,
+                4,
+                offset_of!(StaticVertex, tangent),
+            ),
+        ]);
+    }
+
+    #[test]
+    fn test_animated_vertex_layout() {
+        check_layout::<AnimatedVertex>(&[
+            attribute(Usage::Position, DataType::F32, 3, 0),
+            attribute(Usage::TexCoord0, DataType::F32, 2, 12),
+            attribute(Usage::Normal, DataType::F32, 3, 20),
+            attribute(Usage::Tangent, DataType::F32, 4, 32),
+            attribute(Usage::BoneWeight, DataType::F32, 4, 48),
+            // Bone indices are read as `uvec4`, so they must not be normalized.
+            Expected {
+                integer: true,
+                ..attribute(Usage::BoneIndices, DataType::U8, 4, 64)
             },
-            VertexAttributeDescriptor {
-                usage: VertexAttributeUsage::Normal,
-                data_type: VertexAttributeDataType::F32,
-                size: 3,
-                divisor: 0,
-                shader_location: 2,
+        ]);
+        assert_eq!(size_of::<AnimatedVertex>(), 68);
+    }
+
+    #[test]
+    fn test_simple_vertex_layout() {
+        check_layout::<SimpleVertex>(&[attribute(Usage::Position, DataType::F32, 3, 0)]);
+    }
+
+    #[test]
+    fn test_colored_vertex_layout() {
+        check_layout::<ColoredVertex>(&[
+            attribute(Usage::Position, DataType::F32, 3, 0),
+            Expected {
+                normalized: true,
+                ..attribute(Usage::Color, DataType::U8, 4, 12)
             },
-            VertexAttributeDescriptor {
-                usage: VertexAttributeUsage::Tangent,
-                data_type: VertexAttributeDataType::F32,
-                size: 4,
-                divisor: 0,
-                shader_location: 3,
+        ]);
+    }
+
+    #[test]
+    fn test_vertex_2d_layout() {
+        check_layout::<Vertex2D>(&[
+            attribute(Usage::Position, DataType::F32, 2, 0),
+            attribute(Usage::TexCoord0, DataType::F32, 2, 8),
+            Expected {
+                normalized: true,
+                ..attribute(Usage::Color, DataType::U8, 4, 16)
             },
-        ];
-        &LAYOUT
+        ]);
     }
 }
@@ -9,8 +9,7 @@ use std::marker::PhantomData;
 
 use crate::{
     core::{
-        algebra::{Vector2, Vector3, Vector4},
-        arrayvec::ArrayVec,
+        algebra::{Scalar, Vector2, Vector3, Vector4},
         byteorder::{LittleEndian, ReadBytesExt, WriteBytesExt},
         futures::io::Error,
         visitor::prelude::*,
@@ -21,8 +20,8 @@ use crate::{
 #[derive(Clone, Visit, Default, Debug)]
 pub struct VertexBuffer {
     dense_layout: Vec<VertexAttribute>,
-    sparse_layout: [Option<VertexAttribute>; 13],
-    vertex_size_in_byte: u8, 
+    sparse_layout: [Option<VertexAttribute>; VertexAttributeUsage::COUNT],
+    vertex_size_in_byte: u16,
     vertex_count: u32,
     data: Vec<u8>,
     data_hash: u64,
@@ -61,8 +60,8 @@ impl VertexBuffer{
         let mut dense_layout = Vec::new();
 
         // Validate everything as much as possible and calculate vertex size.
-        let mut sparse_layout = [None; VertexAttributeUsage::Count as usize];
-        let mut vertex_size_bytes = 0u8;
+        let mut sparse_layout = [None; VertexAttributeUsage::COUNT];
+        let mut vertex_size_bytes = 0u16;
         for attribute in layout.iter() {
             if attribute.size < 1 || attribute.size > 4 {
                 return Err(ValidationError::InvalidAttributeSize(
@@ -70,6 +69,8 @@ impl VertexBuffer{
                 ));
             }
 
+            attribute.validate()?;
+
             let vertex_attribute = VertexAttribute {
                 usage: attribute.usage,
                 data_type: attribute.data_type,
@@ -77,14 +78,16 @@ impl VertexBuffer{
                 divisor: attribute.divisor,
                 offset: vertex_size_bytes,
                 shader_location: attribute.shader_location,
+                normalized: attribute.normalized,
+                integer: attribute.integer,
             };
 
             dense_layout.push(vertex_attribute);
 
             // Map dense to sparse layout to increase performance.
-            sparse_layout[attribute.usage as usize] = Some(vertex_attribute);
+            sparse_layout[attribute.usage.index()] = Some(vertex_attribute);
 
-            vertex_size_bytes += attribute.size * attribute.data_type.size();
+            vertex_size_bytes += attribute.data_type.attribute_size(attribute.size) as u16;
         }
 
         let expected_data_size = vertex_count * vertex_size_bytes as usize;
@@ -110,7 +113,7 @@ impl VertexBuffer{
         &self.data
     }
     /// Return vertex size of the buffer.
-    pub fn vertex_size_in_byte(&self) -> u8 {
+    pub fn vertex_size_in_byte(&self) -> u16 {
         self.vertex_size_in_byte
     }
     /// Returns vertex buffer layout.
@@ -140,6 +143,105 @@ impl VertexBuffer{
             vertex_buffer: self,
         }
     }
+
+    /// Splits the buffer into separate streams (structure-of-arrays storage), each stream
+    /// contains attributes with given usages in given order. Attributes that are not listed
+    /// are not copied, so a compact position-only stream for depth passes could be made like so:
+    /// `buffer.deinterleave(&[&[VertexAttributeUsage::Position]])`. Use
+    /// `NativeBufferBuilder::from_vertex_streams` to upload the streams.
+    pub fn deinterleave(
+        &self,
+        streams: &[&[VertexAttributeUsage]],
+    ) -> Result<Vec<VertexBuffer>, ValidationError> {
+        streams
+            .iter()
+            .map(|usages| {
+                let layout = usages
+                    .iter()
+                    .map(|&usage| self.attribute_descriptor(usage))
+                    .collect::<Result<Vec<_>, _>>()?;
+                self.with_layout(&layout)
+            })
+            .collect()
+    }
+
+    /// Merges separate streams into a single interleaved buffer. Attributes of the streams
+    /// are placed one after another in order of the streams. Every stream must have the same
+    /// amount of vertices.
+    pub fn interleave(streams: &[VertexBuffer]) -> Result<VertexBuffer, ValidationError> {
+        let vertex_count = streams.first().map_or(0, |s| s.vertex_count as usize);
+
+        let mut layout = Vec::new();
+        for stream in streams {
+            if stream.vertex_count as usize != vertex_count {
+                return Err(ValidationError::VertexCountMismatch {
+                    expected: vertex_count,
+                    actual: stream.vertex_count as usize,
+                });
+            }
+            layout.extend(stream.dense_layout.iter().map(|a| a.descriptor()));
+        }
+
+        let mut result = VertexBuffer::new(0, &layout, Vec::<u8>::new())?;
+        result.data = Vec::with_capacity(vertex_count * result.vertex_size_in_byte as usize);
+        for i in 0..vertex_count {
+            for stream in streams {
+                let vertex_size = stream.vertex_size_in_byte as usize;
+                result
+                    .data
+                    .extend_from_slice(&stream.data[(i * vertex_size)..((i + 1) * vertex_size)]);
+            }
+        }
+        result.vertex_count = vertex_count as u32;
+        result.data_hash = calculate_data_hash(&result.data);
+
+        Ok(result)
+    }
+
+    fn attribute_descriptor(
+        &self,
+        usage: VertexAttributeUsage,
+    ) -> Result<VertexAttributeDescriptor, ValidationError> {
+        self.sparse_layout
+            .get(usage.index())
+            .and_then(Option::as_ref)
+            .map(|a| a.descriptor())
+            .ok_or(ValidationError::MissingAttribute(usage))
+    }
+
+    // Creates new buffer with given layout and the same vertices. Every attribute of the
+    // layout must exist in this buffer, its data is converted if data type or size differ.
+    fn with_layout(
+        &self,
+        layout: &[VertexAttributeDescriptor],
+    ) -> Result<VertexBuffer, ValidationError> {
+        let mut result = VertexBuffer::new(0, layout, Vec::<u8>::new())?;
+
+        let vertex_count = self.vertex_count as usize;
+        let new_vertex_size = result.vertex_size_in_byte as usize;
+        let mut data = vec![0; vertex_count * new_vertex_size];
+
+        if new_vertex_size > 0 {
+            for (src, dst) in self
+                .data
+                .chunks_exact(self.vertex_size_in_byte as usize)
+                .zip(data.chunks_exact_mut(new_vertex_size))
+            {
+                for dst_attribute in result.dense_layout.iter() {
+                    let src_attribute = self.sparse_layout[dst_attribute.usage.index()]
+                        .as_ref()
+                        .ok_or(ValidationError::MissingAttribute(dst_attribute.usage))?;
+                    convert_attribute_data(src, src_attribute, dst, dst_attribute);
+                }
+            }
+        }
+
+        result.data_hash = calculate_data_hash(&data);
+        result.data = data;
+        result.vertex_count = vertex_count as u32;
+
+        Ok(result)
+    }
 }
 
 /// Vertex attribute is a simple "bridge" between raw data and its interpretation. In
@@ -159,45 +261,126 @@ pub struct VertexAttribute {
     ///  2 - per 2 instances and so on.
     pub divisor: u8,
     /// Offset in bytes from beginning of the vertex.
-    pub offset: u8,
+    pub offset: u16,
     /// Defines location of the attribute in a shader (`layout(location = x) attrib;`)
     pub shader_location: u8,
+    /// Integer values are mapped to `[0; 1]` (unsigned) or `[-1; 1]` (signed) range when
+    /// read as floats in shader.
+    pub normalized: bool,
+    /// Attribute is read as integer (`uint`, `uvec4`, etc.) in shader, without conversion
+    /// to float.
+    pub integer: bool,
 }
 
-/// An usage for vertex attribute. It is a fixed set, but there are plenty
-/// room for any custom data - it may be fit into `TexCoordN` attributes.
+impl VertexAttribute {
+    /// Returns descriptor of the attribute.
+    pub fn descriptor(&self) -> VertexAttributeDescriptor {
+        VertexAttributeDescriptor {
+            usage: self.usage,
+            data_type: self.data_type,
+            size: self.size,
+            divisor: self.divisor,
+            shader_location: self.shader_location,
+            normalized: self.normalized,
+            integer: self.integer,
+        }
+    }
+}
+
+/// Minimum value of `GL_MAX_VERTEX_ATTRIB_STRIDE` guaranteed by OpenGL 4.4 and OpenGL ES 3.1,
+/// it is used as the limit for older drivers. A layout of [`VertexBuffer`] cannot exceed it (every
+/// attribute takes at most 16 bytes), so only the actual limit of the driver is checked when a
+/// native buffer is created.
+pub const MAX_VERTEX_SIZE: u16 = 2048;
+
+/// Maximum amount of custom attributes, see [`VertexAttributeUsage::Custom`].
+pub const MAX_CUSTOM_VERTEX_ATTRIBUTES: usize = 8;
+
+/// An usage for vertex attribute. It is a fixed set of well-known usages and a range of
+/// custom slots for any user data.
 #[derive(Copy, Clone, PartialOrd, PartialEq, Eq, Ord, Hash, Visit, Debug)]
-#[repr(u32)]
 pub enum VertexAttributeUsage {
     /// Vertex position. Usually Vector2<f32> or Vector3<f32>.
-    Position = 0,
+    Position,
     /// Vertex normal. Usually Vector3<f32>, more rare Vector3<u16> (F16).
-    Normal = 1,
+    Normal,
     /// Vertex tangent. Usually Vector3<f32>.
-    Tangent = 2,
+    Tangent,
     /// First texture coordinates. Usually Vector2<f32>.
     /// It may be used for everything else, not only for texture coordinates.
-    TexCoord0 = 3,
+    TexCoord0,
     /// Second texture coordinates.
-    TexCoord1 = 4,
+    TexCoord1,
     /// Third texture coordinates.
-    TexCoord2 = 5,
+    TexCoord2,
     /// Fourth texture coordinates.
-    TexCoord3 = 6,
+    TexCoord3,
     /// Fifth texture coordinates.
-    TexCoord4 = 7,
+    TexCoord4,
     /// Sixth texture coordinates.
-    TexCoord5 = 8,
+    TexCoord5,
     /// Seventh texture coordinates.
-    TexCoord6 = 9,
+    TexCoord6,
     /// Eighth texture coordinates.
-    TexCoord7 = 10,
+    TexCoord7,
     /// Bone weights. Usually Vector4<f32>.
-    BoneWeight = 11,
-    /// Bone indices. Usually Vector4<u8>.
-    BoneIndices = 12,
-    /// Maximum amount of attribute kinds.
-    Count,
+    BoneWeight,
+    /// Bone indices. Usually Vector4<u8>, marked as `integer` to be read as `uvec4`.
+    BoneIndices,
+    /// Vertex color. Usually Vector4<u8>, marked as `normalized` to be read as `vec4`.
+    Color,
+    /// Custom user data, index must be less than [`MAX_CUSTOM_VERTEX_ATTRIBUTES`].
+    Custom(u8),
+}
+
+impl VertexAttributeUsage {
+    /// Total amount of attribute usages, including custom ones.
+    pub const COUNT: usize = 14 + MAX_CUSTOM_VERTEX_ATTRIBUTES;
+
+    /// Returns index of the usage in `[0; COUNT)` range. Custom usages with out-of-range index
+    /// have index `COUNT` or larger, such usages are rejected by layout validation.
+    #[inline]
+    pub fn index(self) -> usize {
+        match self {
+            Self::Position => 0,
+            Self::Normal => 1,
+            Self::Tangent => 2,
+            Self::TexCoord0 => 3,
+            Self::TexCoord1 => 4,
+            Self::TexCoord2 => 5,
+            Self::TexCoord3 => 6,
+            Self::TexCoord4 => 7,
+            Self::TexCoord5 => 8,
+            Self::TexCoord6 => 9,
+            Self::TexCoord7 => 10,
+            Self::BoneWeight => 11,
+            Self::BoneIndices => 12,
+            Self::Color => 13,
+            Self::Custom(n) => 14 + n as usize,
+        }
+    }
+
+    /// Returns usage with given index, see [`Self::index`].
+    pub fn from_index(index: usize) -> Option<Self> {
+        Some(match index {
+            0 => Self::Position,
+            1 => Self::Normal,
+            2 => Self::Tangent,
+            3 => Self::TexCoord0,
+            4 => Self::TexCoord1,
+            5 => Self::TexCoord2,
+            6 => Self::TexCoord3,
+            7 => Self::TexCoord4,
+            8 => Self::TexCoord5,
+            9 => Self::TexCoord6,
+            10 => Self::TexCoord7,
+            11 => Self::BoneWeight,
+            12 => Self::BoneIndices,
+            13 => Self::Color,
+            n if n < Self::COUNT => Self::Custom((n - 14) as u8),
+            _ => return None,
+        })
+    }
 }
 
 /// Data type for a vertex attribute component.
@@ -212,6 +395,20 @@ pub enum VertexAttributeDataType {
     U16,
     /// 8-bit unsigned integer.
     U8,
+    /// 32-bit signed integer.
+    I32,
+    /// 16-bit signed integer.
+    I16,
+    /// 8-bit signed integer.
+    I8,
+    /// 16-bit floating-point.
+    F16,
+    /// Four signed components packed in 32 bits: 10 bits for x, y, z and 2 bits for w.
+    /// Attribute size must be 4.
+    PackedI2_10_10_10Rev,
+    /// Four unsigned components packed in 32 bits: 10 bits for x, y, z and 2 bits for w.
+    /// Attribute size must be 4.
+    PackedU2_10_10_10Rev,
 }
 
 /// Input vertex attribute descriptor used to construct layouts and feed vertex buffer.
@@ -231,6 +428,30 @@ pub struct VertexAttributeDescriptor {
     pub divisor: u8,
     /// Defines location of the attribute in a shader (`layout(location = x) attrib;`)
     pub shader_location: u8,
+    /// Integer values are mapped to `[0; 1]` (unsigned) or `[-1; 1]` (signed) range when
+    /// read as floats in shader. Ignored for floating-point and `integer` attributes.
+    pub normalized: bool,
+    /// Attribute is read as integer (`uint`, `uvec4`, etc.) in shader, without conversion
+    /// to float. Valid for integer data types only.
+    pub integer: bool,
+}
+
+impl VertexAttributeDescriptor {
+    fn validate(&self) -> Result<(), ValidationError> {
+        if let VertexAttributeUsage::Custom(n) = self.usage {
+            if n as usize >= MAX_CUSTOM_VERTEX_ATTRIBUTES {
+                return Err(ValidationError::InvalidCustomAttribute(n));
+            }
+        }
+
+        if self.integer && !self.data_type.is_integer() {
+            Err(ValidationError::NonIntegerDataType(self.usage))
+        } else if self.data_type.is_packed() && self.size != 4 {
+            Err(ValidationError::InvalidAttributeSize(self.size as usize))
+        } else {
+            Ok(())
+        }
+    }
 }
 
 /// An error that may occur during input data and layout validation.
@@ -253,9 +474,9 @@ pub enum ValidationError {
     #[error("Invalid vertex size. Expected {}, got {}.", expected, actual)]
     InvalidVertexSize {
         /// Expected vertex size.
-        expected: u8,
+        expected: u16,
         /// Actual vertex size.
-        actual: u8,
+        actual: usize,
     },
 
     /// A duplicate of a descriptor was found.
@@ -265,6 +486,39 @@ pub enum ValidationError {
     /// Duplicate shader locations were found.
     #[error("Duplicate shader locations were found {0}.")]
     ConflictingShaderLocations(usize),
+
+    /// There is no attribute with given usage in the buffer.
+    #[error("There is no attribute {0:?} in the buffer.")]
+    MissingAttribute(VertexAttributeUsage),
+
+    /// Streams of a buffer have different amount of vertices.
+    #[error("Vertex count mismatch. Expected {}, got {}.", expected, actual)]
+    VertexCountMismatch {
+        /// Expected amount of vertices.
+        expected: usize,
+        /// Actual amount of vertices.
+        actual: usize,
+    },
+
+    /// Vertex is larger than maximum vertex stride supported by the driver.
+    #[error("Vertex size {} exceeds maximum vertex size {}.", size, max)]
+    VertexSizeTooLarge {
+        /// Size of the vertex in bytes.
+        size: usize,
+        /// Maximum size of vertex in bytes.
+        max: usize,
+    },
+
+    /// Index of a custom attribute is out of range.
+    #[error(
+        "Custom attribute index {0} is out of range. Must be less than {}",
+        MAX_CUSTOM_VERTEX_ATTRIBUTES
+    )]
+    InvalidCustomAttribute(u8),
+
+    /// An attribute is marked as integer, but its data type is not integer.
+    #[error("Attribute {0:?} is marked as integer, but its data type is not integer.")]
+    NonIntegerDataType(VertexAttributeUsage),
 }
 
 
@@ -276,14 +530,69 @@ fn calculate_data_hash(data: &[u8]) -> u64 {
 
 
 impl VertexAttributeDataType {
-    /// Returns size of data in bytes.
+    /// Returns data type with given discriminant (`data_type as u8`).
+    pub fn from_u8(value: u8) -> Option<Self> {
+        Some(match value {
+            0 => Self::F32,
+            1 => Self::U32,
+            2 => Self::U16,
+            3 => Self::U8,
+            4 => Self::I32,
+            5 => Self::I16,
+            6 => Self::I8,
+            7 => Self::F16,
+            8 => Self::PackedI2_10_10_10Rev,
+            9 => Self::PackedU2_10_10_10Rev,
+            _ => return None,
+        })
+    }
+
+    /// Returns size of data in bytes. For packed types it is the size of all four
+    /// components.
     pub fn size(self) -> u8 {
         match self {
-            VertexAttributeDataType::F32 | VertexAttributeDataType::U32 => 4,
-            VertexAttributeDataType::U16 => 2,
-            VertexAttributeDataType::U8 => 1,
+            VertexAttributeDataType::F32
+            | VertexAttributeDataType::U32
+            | VertexAttributeDataType::I32
+            | VertexAttributeDataType::PackedI2_10_10_10Rev
+            | VertexAttributeDataType::PackedU2_10_10_10Rev => 4,
+            VertexAttributeDataType::U16
+            | VertexAttributeDataType::I16
+            | VertexAttributeDataType::F16 => 2,
+            VertexAttributeDataType::U8 | VertexAttributeDataType::I8 => 1,
         }
     }
+
+    /// Returns size in bytes of an attribute with given amount of components.
+    pub fn attribute_size(self, components: u8) -> u8 {
+        if self.is_packed() {
+            self.size()
+        } else {
+            components * self.size()
+        }
+    }
+
+    /// Returns true if the type is an integer type which can be read as integer in shader.
+    pub fn is_integer(self) -> bool {
+        matches!(
+            self,
+            VertexAttributeDataType::U32
+                | VertexAttributeDataType::U16
+                | VertexAttributeDataType::U8
+                | VertexAttributeDataType::I32
+                | VertexAttributeDataType::I16
+                | VertexAttributeDataType::I8
+        )
+    }
+
+    /// Returns true if all components of the attribute are packed in a single value.
+    pub fn is_packed(self) -> bool {
+        matches!(
+            self,
+            VertexAttributeDataType::PackedI2_10_10_10Rev
+                | VertexAttributeDataType::PackedU2_10_10_10Rev
+        )
+    }
 }
 
 
@@ -310,6 +619,24 @@ pub enum VertexFetchError {
     /// IO error.
     #[error("An i/o error has occurred {0:?}")]
     Io(std::io::Error),
+    /// Type of the attribute does not match requested type.
+    #[error(
+        "Attribute {:?} of type {:?} with {} components cannot be accessed as {}",
+        usage,
+        data_type,
+        size,
+        requested
+    )]
+    TypeMismatch {
+        /// Usage of the attribute.
+        usage: VertexAttributeUsage,
+        /// Actual data type of the attribute.
+        data_type: VertexAttributeDataType,
+        /// Actual amount of components of the attribute.
+        size: u8,
+        /// Name of the requested type.
+        requested: &'static str,
+    },
 }
 
 impl From<std::io::Error> for VertexFetchError {
@@ -338,62 +665,74 @@ pub trait VertexReadTrait {
     #[doc(hidden)]
     fn data_layout_ref(&self) -> (&[u8], &[Option<VertexAttribute>]);
 
-    /// Tries to read an attribute with given usage as a pair of two f32.
+    /// Tries to read an attribute with given usage as a value of given type. Fails if amount
+    /// of components of the attribute does not match the type, or if its data type cannot be
+    /// converted to the type (see [`VertexAttributeComponent`]).
+    #[inline(always)]
+    fn read<T: VertexAttributeValue>(
+        &self,
+        usage: VertexAttributeUsage,
+    ) -> Result<T, VertexFetchError> {
+        let (data, layout) = self.data_layout_ref();
+        if let Some(attribute) = layout.get(usage.index()).and_then(Option::as_ref) {
+            check_value_type::<T>(attribute)?;
+            let components = T::Component::read(data, attribute)?;
+            Ok(T::from_components(components))
+        } else {
+            Err(VertexFetchError::NoSuchAttribute(usage))
+        }
+    }
+
+    /// Tries to read an attribute with given usage as a pair of two f32. Components are
+    /// converted from the data type of the attribute, which must have exactly two components
+    /// (packed attributes can be accessed partially).
     #[inline(always)]
     fn read_2_f32(&self, usage: VertexAttributeUsage) -> Result<Vector2<f32>, VertexFetchError> {
         let (data, layout) = self.data_layout_ref();
-        if let Some(attribute) = layout.get(usage as usize).unwrap() {
-            let x = (&data[(attribute.offset as usize)..]).read_f32::<LittleEndian>()?;
-            let y = (&data[(attribute.offset as usize + 4)..]).read_f32::<LittleEndian>()?;
+        if let Some(attribute) = layout.get(usage.index()).and_then(Option::as_ref) {
+            check_component_count(attribute, 2, "Vector2<f32>")?;
+            let [x, y] = read_components(data, attribute)?;
             Ok(Vector2::new(x, y))
         } else {
             Err(VertexFetchError::NoSuchAttribute(usage))
         }
     }
 
-    /// Tries to read an attribute with given usage as a pair of three f32.
+    /// Tries to read an attribute with given usage as a pair of three f32. Components are
+    /// converted from the data type of the attribute, which must have exactly three components
+    /// (packed attributes can be accessed partially).
     #[inline(always)]
     fn read_3_f32(&self, usage: VertexAttributeUsage) -> Result<Vector3<f32>, VertexFetchError> {
         let (data, layout) = self.data_layout_ref();
-        if let Some(attribute) = layout.get(usage as usize).unwrap() {
-            let x = (&data[(attribute.offset as usize)..]).read_f32::<LittleEndian>()?;
-            let y = (&data[(attribute.offset as usize + 4)..]).read_f32::<LittleEndian>()?;
-            let z = (&data[(attribute.offset as usize + 8)..]).read_f32::<LittleEndian>()?;
+        if let Some(attribute) = layout.get(usage.index()).and_then(Option::as_ref) {
+            check_component_count(attribute, 3, "Vector3<f32>")?;
+            let [x, y, z] = read_components(data, attribute)?;
             Ok(Vector3::new(x, y, z))
         } else {
             Err(VertexFetchError::NoSuchAttribute(usage))
         }
     }
 
-    /// Tries to read an attribute with given usage as a pair of four f32.
+    /// Tries to read an attribute with given usage as a pair of four f32. Components are
+    /// converted from the data type of the attribute, which must have exactly four components
+    /// (packed attributes can be accessed partially).
     #[inline(always)]
     fn read_4_f32(&self, usage: VertexAttributeUsage) -> Result<Vector4<f32>, VertexFetchError> {
         let (data, layout) = self.data_layout_ref();
-        if let Some(attribute) = layout.get(usage as usize).unwrap() {
-            let x = (&data[(attribute.offset as usize)..]).read_f32::<LittleEndian>()?;
-            let y = (&data[(attribute.offset as usize + 4)..]).read_f32::<LittleEndian>()?;
-            let z = (&data[(attribute.offset as usize + 8)..]).read_f32::<LittleEndian>()?;
-            let w = (&data[(attribute.offset as usize + 12)..]).read_f32::<LittleEndian>()?;
+        if let Some(attribute) = layout.get(usage.index()).and_then(Option::as_ref) {
+            check_component_count(attribute, 4, "Vector4<f32>")?;
+            let [x, y, z, w] = read_components(data, attribute)?;
             Ok(Vector4::new(x, y, z, w))
         } else {
             Err(VertexFetchError::NoSuchAttribute(usage))
         }
     }
 
-    /// Tries to read an attribute with given usage as a pair of four u8.
+    /// Tries to read an attribute with given usage as a pair of four u8. The attribute must
+    /// have exactly four `U8` components.
     #[inline(always)]
     fn read_4_u8(&self, usage: VertexAttributeUsage) -> Result<Vector4<u8>, VertexFetchError> {
-        let (data, layout) = self.data_layout_ref();
-        if let Some(attribute) = layout.get(usage as usize).unwrap() {
-            let offset = attribute.offset as usize;
-            let x = data[offset];
-            let y = data[offset + 1];
-            let z = data[offset + 2];
-            let w = data[offset + 3];
-            Ok(Vector4::new(x, y, z, w))
-        } else {
-            Err(VertexFetchError::NoSuchAttribute(usage))
-        }
+        self.read(usage)
     }
 }
 
@@ -448,7 +787,7 @@ impl<'a> VertexBufferRefMut<'a> {
         } else {
             Err(ValidationError::InvalidVertexSize {
                 expected: self.vertex_buffer.vertex_size_in_byte,
-                actual: std::mem::size_of::<T>() as u8,
+                actual: std::mem::size_of::<T>(),
             })
         }
     }
@@ -490,7 +829,7 @@ impl<'a> VertexBufferRefMut<'a> {
         } else {
             Err(ValidationError::InvalidVertexSize {
                 expected: self.vertex_buffer.vertex_size_in_byte,
-                actual: std::mem::size_of::<T>() as u8,
+                actual: std::mem::size_of::<T>(),
             })
         }
     }
@@ -508,7 +847,7 @@ impl<'a> VertexBufferRefMut<'a> {
         } else {
             Err(ValidationError::InvalidVertexSize {
                 expected: self.vertex_buffer.vertex_size_in_byte,
-                actual: std::mem::size_of::<T>() as u8,
+                actual: std::mem::size_of::<T>(),
             })
         }
     }
@@ -545,15 +884,10 @@ impl<'a> VertexBufferRefMut<'a> {
 
     /// Duplicates n-th vertex and puts it at the back of the buffer.
     pub fn duplicate(&mut self, n: usize) {
-        // Vertex cannot be larger than 256 bytes, so having temporary array of
-        // such size is ok.
-        let mut temp = ArrayVec::<u8, 256>::new();
-        temp.try_extend_from_slice(
-            &self.vertex_buffer.data[(n * self.vertex_buffer.vertex_size_in_byte as usize)
-                ..((n + 1) * self.vertex_buffer.vertex_size_in_byte as usize)],
-        )
-        .unwrap();
-        self.vertex_buffer.data.extend_from_slice(temp.as_slice());
+        let vertex_size = self.vertex_buffer.vertex_size_in_byte as usize;
+        self.vertex_buffer
+            .data
+            .extend_from_within((n * vertex_size)..((n + 1) * vertex_size));
         self.vertex_buffer.vertex_count += 1;
     }
 
@@ -564,14 +898,36 @@ impl<'a> VertexBufferRefMut<'a> {
     /// Add secondary texture coordinates:
     ///  Before: P1_N1_TC1_P2_N2_TC2...
     ///  After: P1_N1_TC1_TC2(fill_value)_P2_N2_TC2_TC2(fill_value)...
+    ///
+    /// Size of `T` must match the size of the attribute described by `descriptor`.
     pub fn add_attribute<T: Copy>(
         &mut self,
         descriptor: VertexAttributeDescriptor,
         fill_value: T,
     ) -> Result<(), ValidationError> {
-        if self.vertex_buffer.sparse_layout[descriptor.usage as usize].is_some() {
+        if descriptor.size < 1 || descriptor.size > 4 {
+            return Err(ValidationError::InvalidAttributeSize(
+                descriptor.size as usize,
+            ));
+        }
+
+        descriptor.validate()?;
+
+        let attribute_size = descriptor.data_type.attribute_size(descriptor.size) as usize;
+        if std::mem::size_of::<T>() != attribute_size {
+            return Err(ValidationError::InvalidDataSize {
+                expected: attribute_size,
+                actual: std::mem::size_of::<T>(),
+            });
+        }
+
+        let vertex_size = self.vertex_buffer.vertex_size_in_byte as usize;
+        let new_vertex_size = vertex_size + attribute_size;
+
+        if self.vertex_buffer.sparse_layout[descriptor.usage.index()].is_some() {
             Err(ValidationError::DuplicatedAttributeDescriptor)
         } else {
+
             let vertex_attribute = VertexAttribute {
                 usage: descriptor.usage,
                 data_type: descriptor.data_type,
@@ -579,32 +935,111 @@ impl<'a> VertexBufferRefMut<'a> {
                 divisor: descriptor.divisor,
                 offset: self.vertex_buffer.vertex_size_in_byte,
                 shader_location: descriptor.shader_location,
+                normalized: descriptor.normalized,
+                integer: descriptor.integer,
             };
-            self.vertex_buffer.sparse_layout[descriptor.usage as usize] = Some(vertex_attribute);
+            self.vertex_buffer.sparse_layout[descriptor.usage.index()] = Some(vertex_attribute);
             self.vertex_buffer.dense_layout.push(vertex_attribute);
 
-            let mut new_data = Vec::new();
+            let mut new_data =
+                Vec::with_capacity(self.vertex_buffer.vertex_count as usize * new_vertex_size);
 
-            for chunk in self
-                .vertex_buffer
-                .data
-                .chunks_exact(self.vertex_buffer.vertex_size_in_byte as usize)
-            {
-                let mut temp = ArrayVec::<u8, 256>::new();
-                temp.try_extend_from_slice(chunk).unwrap();
-                temp.try_extend_from_slice(value_as_u8_slice(&fill_value))
-                    .unwrap();
-                new_data.extend_from_slice(&temp);
+            for chunk in self.vertex_buffer.data.chunks_exact(vertex_size) {
+                new_data.extend_from_slice(chunk);
+                new_data.extend_from_slice(value_as_u8_slice(&fill_value));
             }
 
             self.vertex_buffer.data = new_data;
 
-            self.vertex_buffer.vertex_size_in_byte += std::mem::size_of::<T>() as u8;
+            self.vertex_buffer.vertex_size_in_byte = new_vertex_size as u16;
 
             Ok(())
         }
     }
 
+    /// Removes an attribute with given usage from the layout, the rest of attributes are
+    /// packed tightly.
+    ///
+    /// Remove tangents:
+    ///  Before: P1_N1_T1_P2_N2_T2...
+    ///  After: P1_N1_P2_N2...
+    pub fn remove_attribute(&mut self, usage: VertexAttributeUsage) -> Result<(), ValidationError> {
+        self.vertex_buffer.attribute_descriptor(usage)?;
+
+        let layout = self
+            .vertex_buffer
+            .dense_layout
+            .iter()
+            .filter(|a| a.usage != usage)
+            .map(|a| a.descriptor())
+            .collect::<Vec<_>>();
+
+        *self.vertex_buffer = self.vertex_buffer.with_layout(&layout)?;
+
+        Ok(())
+    }
+
+    /// Converts an attribute with given usage to a new data type, for example F32 normals to
+    /// `PackedI2_10_10_10Rev` to save memory. Integer data types of non-`integer` attributes
+    /// become normalized, components are clamped to the range of the new data type. Packed
+    /// data types always have 4 components, missing components are filled with zeros.
+    pub fn convert_attribute(
+        &mut self,
+        usage: VertexAttributeUsage,
+        data_type: VertexAttributeDataType,
+    ) -> Result<(), ValidationError> {
+        self.vertex_buffer.attribute_descriptor(usage)?;
+
+        let layout = self
+            .vertex_buffer
+            .dense_layout
+            .iter()
+            .map(|a| {
+                let mut descriptor = a.descriptor();
+                if a.usage == usage {
+                    descriptor.data_type = data_type;
+                    descriptor.normalized = match data_type {
+                        VertexAttributeDataType::F32 | VertexAttributeDataType::F16 => false,
+                        _ => !descriptor.integer,
+                    };
+                    if data_type.is_packed() {
+                        descriptor.size = 4;
+                    }
+                }
+                descriptor
+            })
+            .collect::<Vec<_>>();
+
+        *self.vertex_buffer = self.vertex_buffer.with_layout(&layout)?;
+
+        Ok(())
+    }
+
+    /// Changes order of attributes in the layout. `usages` must contain every attribute of the
+    /// buffer exactly once.
+    pub fn reorder_layout(
+        &mut self,
+        usages: &[VertexAttributeUsage],
+    ) -> Result<(), ValidationError> {
+        let layout = usages
+            .iter()
+            .map(|&usage| self.vertex_buffer.attribute_descriptor(usage))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        if let Some(missing) = self
+            .vertex_buffer
+            .dense_layout
+            .iter()
+            .find(|a| !usages.contains(&a.usage))
+        {
+            return Err(ValidationError::MissingAttribute(missing.usage));
+        }
+
+        *self.vertex_buffer = self.vertex_buffer.with_layout(&layout)?;
+
+        Ok(())
+    }
+
     /// Clears the buffer making it empty.
     pub fn clear(&mut self) {
         self.data.clear();
@@ -644,10 +1079,9 @@ impl<'a> VertexWriteTrait for VertexViewMut<'a> {
         value: Vector2<f32>,
     ) -> Result<(), VertexFetchError> {
         let (data, layout) = self.data_layout_mut();
-        if let Some(attribute) = layout.get(usage as usize).unwrap() {
-            (&mut data[(attribute.offset as usize)..]).write_f32::<LittleEndian>(value.x)?;
-            (&mut data[(attribute.offset as usize + 4)..]).write_f32::<LittleEndian>(value.y)?;
-            Ok(())
+        if let Some(attribute) = layout.get(usage.index()).and_then(Option::as_ref) {
+            check_component_count(attribute, 2, "Vector2<f32>")?;
+            write_components(data, attribute, &[value.x, value.y])
         } else {
             Err(VertexFetchError::NoSuchAttribute(usage))
         }
@@ -659,11 +1093,9 @@ impl<'a> VertexWriteTrait for VertexViewMut<'a> {
         value: Vector3<f32>,
     ) -> Result<(), VertexFetchError> {
         let (data, layout) = self.data_layout_mut();
-        if let Some(attribute) = layout.get(usage as usize).unwrap() {
-            (&mut data[(attribute.offset as usize)..]).write_f32::<LittleEndian>(value.x)?;
-            (&mut data[(attribute.offset as usize + 4)..]).write_f32::<LittleEndian>(value.y)?;
-            (&mut data[(attribute.offset as usize + 8)..]).write_f32::<LittleEndian>(value.z)?;
-            Ok(())
+        if let Some(attribute) = layout.get(usage.index()).and_then(Option::as_ref) {
+            check_component_count(attribute, 3, "Vector3<f32>")?;
+            write_components(data, attribute, &[value.x, value.y, value.z])
         } else {
             Err(VertexFetchError::NoSuchAttribute(usage))
         }
@@ -675,12 +1107,9 @@ impl<'a> VertexWriteTrait for VertexViewMut<'a> {
         value: Vector4<f32>,
     ) -> Result<(), VertexFetchError> {
         let (data, layout) = self.data_layout_mut();
-        if let Some(attribute) = layout.get(usage as usize).unwrap() {
-            (&mut data[(attribute.offset as usize)..]).write_f32::<LittleEndian>(value.x)?;
-            (&mut data[(attribute.offset as usize + 4)..]).write_f32::<LittleEndian>(value.y)?;
-            (&mut data[(attribute.offset as usize + 8)..]).write_f32::<LittleEndian>(value.z)?;
-            (&mut data[(attribute.offset as usize + 12)..]).write_f32::<LittleEndian>(value.w)?;
-            Ok(())
+        if let Some(attribute) = layout.get(usage.index()).and_then(Option::as_ref) {
+            check_component_count(attribute, 4, "Vector4<f32>")?;
+            write_components(data, attribute, &[value.x, value.y, value.z, value.w])
         } else {
             Err(VertexFetchError::NoSuchAttribute(usage))
         }
@@ -691,16 +1120,7 @@ impl<'a> VertexWriteTrait for VertexViewMut<'a> {
         usage: VertexAttributeUsage,
         value: Vector4<u8>,
     ) -> Result<(), VertexFetchError> {
-        let (data, layout) = self.data_layout_mut();
-        if let Some(attribute) = layout.get(usage as usize).unwrap() {
-            data[attribute.offset as usize] = value.x;
-            data[(attribute.offset + 1) as usize] = value.y;
-            data[(attribute.offset + 2) as usize] = value.z;
-            data[(attribute.offset + 3) as usize] = value.w;
-            Ok(())
-        } else {
-            Err(VertexFetchError::NoSuchAttribute(usage))
-        }
+        self.write(usage, value)
     }
 }
 
@@ -709,7 +1129,7 @@ struct VertexViewMutIterator<'a> {
     ptr: *mut u8,
     sparse_layout: &'a [Option<VertexAttribute>],
     end: *mut u8,
-    vertex_size: u8,
+    vertex_size: u16,
     marker: PhantomData<&'a mut u8>,
 }
 
@@ -739,31 +1159,1049 @@ pub trait VertexWriteTrait: VertexReadTrait {
     #[doc(hidden)]
     fn data_layout_mut(&mut self) -> (&mut [u8], &[Option<VertexAttribute>]);
 
-    /// Tries to write an attribute with given usage as a pair of two f32.
+    /// Tries to write an attribute with given usage as a value of given type. Type checks
+    /// are the same as in [`VertexReadTrait::read`].
+    #[inline(always)]
+    fn write<T: VertexAttributeValue>(
+        &mut self,
+        usage: VertexAttributeUsage,
+        value: T,
+    ) -> Result<(), VertexFetchError> {
+        let (data, layout) = self.data_layout_mut();
+        if let Some(attribute) = layout.get(usage.index()).and_then(Option::as_ref) {
+            check_value_type::<T>(attribute)?;
+            let components = value.into_components();
+            T::Component::write(data, attribute, &components[..T::COMPONENTS as usize])
+        } else {
+            Err(VertexFetchError::NoSuchAttribute(usage))
+        }
+    }
+
+    /// Tries to write an attribute with given usage as a pair of two f32. Components are
+    /// converted to the data type of the attribute, which must have exactly two components
+    /// (packed attributes can be accessed partially).
     fn write_2_f32(
         &mut self,
         usage: VertexAttributeUsage,
         value: Vector2<f32>,
     ) -> Result<(), VertexFetchError>;
 
-    /// Tries to write an attribute with given usage as a pair of three f32.
+    /// Tries to write an attribute with given usage as a pair of three f32. Components are
+    /// converted to the data type of the attribute, which must have exactly three components
+    /// (packed attributes can be accessed partially).
     fn write_3_f32(
         &mut self,
         usage: VertexAttributeUsage,
         value: Vector3<f32>,
     ) -> Result<(), VertexFetchError>;
 
-    /// Tries to write an attribute with given usage as a pair of four f32.
+    /// Tries to write an attribute with given usage as a pair of four f32. Components are
+    /// converted to the data type of the attribute, which must have exactly four components
+    /// (packed attributes can be accessed partially).
     fn write_4_f32(
         &mut self,
         usage: VertexAttributeUsage,
         value: Vector4<f32>,
     ) -> Result<(), VertexFetchError>;
 
-    /// Tries to write an attribute with given usage as a pair of four u8.
+    /// Tries to write an attribute with given usage as a pair of four u8. The attribute must
+    /// have exactly four `U8` components.
     fn write_4_u8(
         &mut self,
         usage: VertexAttributeUsage,
         value: Vector4<u8>,
     ) -> Result<(), VertexFetchError>;
 }
+
+
+/// A component of [`VertexAttributeValue`]. `f32` can be read from floating-point, packed and
+/// normalized integer attributes with conversion, integer components require exact match of
+/// the data type.
+pub trait VertexAttributeComponent: Copy + Default {
+    /// Returns true if a component of the attribute can be converted to this type.
+    fn is_compatible(data_type: VertexAttributeDataType, normalized: bool) -> bool;
+
+    #[doc(hidden)]
+    fn read(data: &[u8], attribute: &VertexAttribute) -> Result<[Self; 4], VertexFetchError>;
+
+    #[doc(hidden)]
+    fn write(
+        data: &mut [u8],
+        attribute: &VertexAttribute,
+        components: &[Self],
+    ) -> Result<(), VertexFetchError>;
+}
+
+impl VertexAttributeComponent for f32 {
+    fn is_compatible(data_type: VertexAttributeDataType, normalized: bool) -> bool {
+        match data_type {
+            VertexAttributeDataType::F32
+            | VertexAttributeDataType::F16
+            | VertexAttributeDataType::PackedI2_10_10_10Rev
+            | VertexAttributeDataType::PackedU2_10_10_10Rev => true,
+            _ => normalized,
+        }
+    }
+
+    fn read(data: &[u8], attribute: &VertexAttribute) -> Result<[Self; 4], VertexFetchError> {
+        if attribute.data_type.is_packed() {
+            return read_components(data, attribute);
+        }
+
+        let mut reader = &data[(attribute.offset as usize)..];
+        let mut components = [0.0; 4];
+        for component in components.iter_mut().take(attribute.size as usize) {
+            *component = read_component(&mut reader, attribute.data_type, attribute.normalized)?;
+        }
+        Ok(components)
+    }
+
+    fn write(
+        data: &mut [u8],
+        attribute: &VertexAttribute,
+        components: &[Self],
+    ) -> Result<(), VertexFetchError> {
+        write_components(data, attribute, components)
+    }
+}
+
+macro_rules! impl_integer_component {
+    ($($ty:ty => $data_type:ident),*) => {
+        $(
+            impl VertexAttributeComponent for $ty {
+                fn is_compatible(data_type: VertexAttributeDataType, _normalized: bool) -> bool {
+                    data_type == VertexAttributeDataType::$data_type
+                }
+
+                fn read(
+                    data: &[u8],
+                    attribute: &VertexAttribute,
+                ) -> Result<[Self; 4], VertexFetchError> {
+                    const SIZE: usize = std::mem::size_of::<$ty>();
+                    let mut components = [0; 4];
+                    for (i, component) in components
+                        .iter_mut()
+                        .take(attribute.size as usize)
+                        .enumerate()
+                    {
+                        let offset = attribute.offset as usize + i * SIZE;
+                        let mut bytes = [0; SIZE];
+                        bytes.copy_from_slice(&data[offset..(offset + SIZE)]);
+                        *component = <$ty>::from_le_bytes(bytes);
+                    }
+                    Ok(components)
+                }
+
+                fn write(
+                    data: &mut [u8],
+                    attribute: &VertexAttribute,
+                    components: &[Self],
+                ) -> Result<(), VertexFetchError> {
+                    const SIZE: usize = std::mem::size_of::<$ty>();
+                    for (i, component) in components.iter().enumerate() {
+                        let offset = attribute.offset as usize + i * SIZE;
+                        data[offset..(offset + SIZE)].copy_from_slice(&component.to_le_bytes());
+                    }
+                    Ok(())
+                }
+            }
+        )*
+    };
+}
+
+impl_integer_component!(
+    u8 => U8,
+    u16 => U16,
+    u32 => U32,
+    i8 => I8,
+    i16 => I16,
+    i32 => I32
+);
+
+/// A value that can be read from or written to a vertex attribute: a scalar or a vector of
+/// [`VertexAttributeComponent`]s. Amount of components must match the size of the attribute.
+pub trait VertexAttributeValue: Sized {
+    /// Type of a single component of the value.
+    type Component: VertexAttributeComponent;
+
+    /// Amount of components of the value.
+    const COMPONENTS: u8;
+
+    #[doc(hidden)]
+    fn from_components(components: [Self::Component; 4]) -> Self;
+
+    #[doc(hidden)]
+    fn into_components(self) -> [Self::Component; 4];
+}
+
+macro_rules! impl_scalar_value {
+    ($($ty:ty),*) => {
+        $(
+            impl VertexAttributeValue for $ty {
+                type Component = $ty;
+
+                const COMPONENTS: u8 = 1;
+
+                fn from_components(components: [Self::Component; 4]) -> Self {
+                    components[0]
+                }
+
+                fn into_components(self) -> [Self::Component; 4] {
+                    [self, Default::default(), Default::default(), Default::default()]
+                }
+            }
+        )*
+    };
+}
+
+impl_scalar_value!(f32, u8, u16, u32, i8, i16, i32);
+
+impl<T: VertexAttributeComponent + Scalar> VertexAttributeValue for Vector2<T> {
+    type Component = T;
+
+    const COMPONENTS: u8 = 2;
+
+    fn from_components(components: [Self::Component; 4]) -> Self {
+        Vector2::new(components[0], components[1])
+    }
+
+    fn into_components(self) -> [Self::Component; 4] {
+        [self.x, self.y, Default::default(), Default::default()]
+    }
+}
+
+impl<T: VertexAttributeComponent + Scalar> VertexAttributeValue for Vector3<T> {
+    type Component = T;
+
+    const COMPONENTS: u8 = 3;
+
+    fn from_components(components: [Self::Component; 4]) -> Self {
+        Vector3::new(components[0], components[1], components[2])
+    }
+
+    fn into_components(self) -> [Self::Component; 4] {
+        [self.x, self.y, self.z, Default::default()]
+    }
+}
+
+impl<T: VertexAttributeComponent + Scalar> VertexAttributeValue for Vector4<T> {
+    type Component = T;
+
+    const COMPONENTS: u8 = 4;
+
+    fn from_components(components: [Self::Component; 4]) -> Self {
+        Vector4::new(components[0], components[1], components[2], components[3])
+    }
+
+    fn into_components(self) -> [Self::Component; 4] {
+        [self.x, self.y, self.z, self.w]
+    }
+}
+
+fn check_value_type<T: VertexAttributeValue>(
+    attribute: &VertexAttribute,
+) -> Result<(), VertexFetchError> {
+    if attribute.size == T::COMPONENTS
+        && T::Component::is_compatible(attribute.data_type, attribute.normalized)
+    {
+        Ok(())
+    } else {
+        Err(VertexFetchError::TypeMismatch {
+            usage: attribute.usage,
+            data_type: attribute.data_type,
+            size: attribute.size,
+            requested: std::any::type_name::<T>(),
+        })
+    }
+}
+
+// Checks that the attribute has exactly `count` components. Packed attributes always have
+// four components, so they can be accessed partially.
+fn check_component_count(
+    attribute: &VertexAttribute,
+    count: u8,
+    requested: &'static str,
+) -> Result<(), VertexFetchError> {
+    if attribute.size == count || (attribute.data_type.is_packed() && count <= 4) {
+        Ok(())
+    } else {
+        Err(VertexFetchError::TypeMismatch {
+            usage: attribute.usage,
+            data_type: attribute.data_type,
+            size: attribute.size,
+            requested,
+        })
+    }
+}
+
+// Copies an attribute from one vertex to another, converting its data if data types or sizes
+// of the attributes differ. Extra components of the destination are left intact.
+fn convert_attribute_data(
+    src: &[u8],
+    src_attribute: &VertexAttribute,
+    dst: &mut [u8],
+    dst_attribute: &VertexAttribute,
+) {
+    if src_attribute.data_type == dst_attribute.data_type
+        && src_attribute.size == dst_attribute.size
+        && src_attribute.normalized == dst_attribute.normalized
+    {
+        let size = src_attribute.data_type.attribute_size(src_attribute.size) as usize;
+        let src_offset = src_attribute.offset as usize;
+        let dst_offset = dst_attribute.offset as usize;
+        dst[dst_offset..(dst_offset + size)].copy_from_slice(&src[src_offset..(src_offset + size)]);
+    } else {
+        // Both slices are sized by their layouts, so neither read nor write can fail.
+        let components = <f32 as VertexAttributeComponent>::read(src, src_attribute).unwrap();
+        let count = src_attribute.size.min(dst_attribute.size) as usize;
+        write_components(dst, dst_attribute, &components[..count]).unwrap();
+    }
+}
+
+// Reads N components of the attribute as f32, converting them from the data type of the
+// attribute.
+fn read_components<const N: usize>(
+    data: &[u8],
+    attribute: &VertexAttribute,
+) -> Result<[f32; N], VertexFetchError> {
+    let mut reader = &data[(attribute.offset as usize)..];
+    let mut components = [0.0; N];
+
+    if attribute.data_type.is_packed() {
+        let packed = reader.read_u32::<LittleEndian>()?;
+        let unpacked = unpack_2_10_10_10(packed, attribute.data_type, attribute.normalized);
+        components.copy_from_slice(&unpacked[..N]);
+    } else {
+        for component in components.iter_mut() {
+            *component = read_component(&mut reader, attribute.data_type, attribute.normalized)?;
+        }
+    }
+
+    Ok(components)
+}
+
+// Writes components of the attribute converting them to the data type of the attribute.
+// Components of packed attribute that are not in `components` are kept intact.
+fn write_components(
+    data: &mut [u8],
+    attribute: &VertexAttribute,
+    components: &[f32],
+) -> Result<(), VertexFetchError> {
+    let offset = attribute.offset as usize;
+
+    if attribute.data_type.is_packed() {
+        let packed = (&data[offset..]).read_u32::<LittleEndian>()?;
+        let mut unpacked = unpack_2_10_10_10(packed, attribute.data_type, attribute.normalized);
+        unpacked[..components.len()].copy_from_slice(components);
+        let packed = pack_2_10_10_10(unpacked, attribute.data_type, attribute.normalized);
+        (&mut data[offset..]).write_u32::<LittleEndian>(packed)?;
+    } else {
+        let mut writer = &mut data[offset..];
+        for &component in components {
+            write_component(&mut writer, attribute.data_type, attribute.normalized, component)?;
+        }
+    }
+
+    Ok(())
+}
+
+fn read_component(
+    reader: &mut &[u8],
+    data_type: VertexAttributeDataType,
+    normalized: bool,
+) -> std::io::Result<f32> {
+    Ok(match data_type {
+        VertexAttributeDataType::F32 => reader.read_f32::<LittleEndian>()?,
+        VertexAttributeDataType::F16 => f16_to_f32(reader.read_u16::<LittleEndian>()?),
+        VertexAttributeDataType::U32 => {
+            from_unsigned(reader.read_u32::<LittleEndian>()?, u32::MAX, normalized)
+        }
+        VertexAttributeDataType::U16 => from_unsigned(
+            reader.read_u16::<LittleEndian>()? as u32,
+            u16::MAX as u32,
+            normalized,
+        ),
+        VertexAttributeDataType::U8 => {
+            from_unsigned(reader.read_u8()? as u32, u8::MAX as u32, normalized)
+        }
+        VertexAttributeDataType::I32 => {
+            from_signed(reader.read_i32::<LittleEndian>()?, i32::MAX, normalized)
+        }
+        VertexAttributeDataType::I16 => from_signed(
+            reader.read_i16::<LittleEndian>()? as i32,
+            i16::MAX as i32,
+            normalized,
+        ),
+        VertexAttributeDataType::I8 => {
+            from_signed(reader.read_i8()? as i32, i8::MAX as i32, normalized)
+        }
+        VertexAttributeDataType::PackedI2_10_10_10Rev
+        | VertexAttributeDataType::PackedU2_10_10_10Rev => unreachable!(),
+    })
+}
+
+fn write_component(
+    writer: &mut &mut [u8],
+    data_type: VertexAttributeDataType,
+    normalized: bool,
+    value: f32,
+) -> std::io::Result<()> {
+    match data_type {
+        VertexAttributeDataType::F32 => writer.write_f32::<LittleEndian>(value),
+        VertexAttributeDataType::F16 => writer.write_u16::<LittleEndian>(f32_to_f16(value)),
+        VertexAttributeDataType::U32 => {
+            writer.write_u32::<LittleEndian>(to_unsigned(value, u32::MAX, normalized))
+        }
+        VertexAttributeDataType::U16 => writer
+            .write_u16::<LittleEndian>(to_unsigned(value, u16::MAX as u32, normalized) as u16),
+        VertexAttributeDataType::U8 => {
+            writer.write_u8(to_unsigned(value, u8::MAX as u32, normalized) as u8)
+        }
+        VertexAttributeDataType::I32 => {
+            writer.write_i32::<LittleEndian>(to_signed(value, i32::MAX, normalized))
+        }
+        VertexAttributeDataType::I16 => writer
+            .write_i16::<LittleEndian>(to_signed(value, i16::MAX as i32, normalized) as i16),
+        VertexAttributeDataType::I8 => {
+            writer.write_i8(to_signed(value, i8::MAX as i32, normalized) as i8)
+        }
+        VertexAttributeDataType::PackedI2_10_10_10Rev
+        | VertexAttributeDataType::PackedU2_10_10_10Rev => unreachable!(),
+    }
+}
+
+// Normalization rules are the same as in GL: unsigned values are mapped to [0; 1], signed
+// to [-1; 1] (the minimal value is clamped to -1).
+fn from_unsigned(value: u32, max: u32, normalized: bool) -> f32 {
+    if normalized {
+        value as f32 / max as f32
+    } else {
+        value as f32
+    }
+}
+
+fn from_signed(value: i32, max: i32, normalized: bool) -> f32 {
+    if normalized {
+        (value as f32 / max as f32).max(-1.0)
+    } else {
+        value as f32
+    }
+}
+
+fn to_unsigned(value: f32, max: u32, normalized: bool) -> u32 {
+    let value = if normalized {
+        value.clamp(0.0, 1.0) * max as f32
+    } else {
+        value
+    };
+    value.round().clamp(0.0, max as f32) as u32
+}
+
+fn to_signed(value: f32, max: i32, normalized: bool) -> i32 {
+    let value = if normalized {
+        value.clamp(-1.0, 1.0) * max as f32
+    } else {
+        value
+    };
+    value.round().clamp(-(max as f32) - 1.0, max as f32) as i32
+}
+
+// Layout of 2_10_10_10_REV: x in bits 0..10, y in 10..20, z in 20..30, w in 30..32.
+const PACKED_BITS: [u32; 4] = [10, 10, 10, 2];
+
+fn unpack_2_10_10_10(
+    packed: u32,
+    data_type: VertexAttributeDataType,
+    normalized: bool,
+) -> [f32; 4] {
+    let mut components = [0.0; 4];
+    let mut shift = 0;
+    for (component, bits) in components.iter_mut().zip(PACKED_BITS) {
+        let value = (packed >> shift) & ((1 << bits) - 1);
+        *component = if data_type == VertexAttributeDataType::PackedI2_10_10_10Rev {
+            // Sign-extend.
+            let value = ((value << (32 - bits)) as i32) >> (32 - bits);
+            from_signed(value, (1 << (bits - 1)) - 1, normalized)
+        } else {
+            from_unsigned(value, (1 << bits) - 1, normalized)
+        };
+        shift += bits;
+    }
+    components
+}
+
+fn pack_2_10_10_10(
+    components: [f32; 4],
+    data_type: VertexAttributeDataType,
+    normalized: bool,
+) -> u32 {
+    let mut packed = 0;
+    let mut shift = 0;
+    for (component, bits) in components.into_iter().zip(PACKED_BITS) {
+        let mask = (1 << bits) - 1;
+        let value = if data_type == VertexAttributeDataType::PackedI2_10_10_10Rev {
+            to_signed(component, (1 << (bits - 1)) - 1, normalized) as u32 & mask
+        } else {
+            to_unsigned(component, mask, normalized)
+        };
+        packed |= value << shift;
+        shift += bits;
+    }
+    packed
+}
+
+fn f16_to_f32(half: u16) -> f32 {
+    let sign = ((half >> 15) as u32) << 31;
+    let exponent = ((half >> 10) & 0x1F) as u32;
+    let mantissa = (half & 0x3FF) as u32;
+
+    let bits = match exponent {
+        0 if mantissa == 0 => sign,
+        0 => {
+            // Subnormal, normalize it.
+            let mut mantissa = mantissa;
+            let mut shift = 0;
+            while mantissa & 0x400 == 0 {
+                mantissa <<= 1;
+                shift += 1;
+            }
+            sign | ((113 - shift) << 23) | ((mantissa & 0x3FF) << 13)
+        }
+        // Infinity or NaN.
+        0x1F => sign | 0x7F80_0000 | (mantissa << 13),
+        _ => sign | ((exponent + 112) << 23) | (mantissa << 13),
+    };
+
+    f32::from_bits(bits)
+}
+
+fn f32_to_f16(value: f32) -> u16 {
+    let bits = value.to_bits();
+    let sign = ((bits >> 16) & 0x8000) as u16;
+    let exponent = ((bits >> 23) & 0xFF) as i32;
+    let mantissa = bits & 0x7F_FFFF;
+
+    if exponent == 0xFF {
+        // Infinity or NaN.
+        return sign | 0x7C00 | if mantissa != 0 { 0x200 } else { 0 };
+    }
+
+    let exponent = exponent - 127 + 15;
+    if exponent >= 0x1F {
+        // Too large, becomes infinity.
+        sign | 0x7C00
+    } else if exponent <= 0 {
+        if exponent < -10 {
+            // Too small, becomes zero.
+            sign
+        } else {
+            // Subnormal.
+            let mantissa = mantissa | 0x80_0000;
+            let shift = (14 - exponent) as u32;
+            let half = mantissa >> shift;
+            let round = (mantissa >> (shift - 1)) & 1;
+            sign | (half + round) as u16
+        }
+    } else {
+        // Rounding may carry into exponent, it is still correct.
+        let half = ((exponent as u32) << 10) | (mantissa >> 13);
+        let round = (mantissa >> 12) & 1;
+        sign | (half + round) as u16
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        core::algebra::{Vector2, Vector3, Vector4},
+        vertex_buffer::{
+            f16_to_f32, f32_to_f16, pack_2_10_10_10, unpack_2_10_10_10, ValidationError,
+            VertexAttributeDataType, VertexAttributeDescriptor, VertexAttributeUsage, VertexBuffer,
+            VertexReadTrait,
+        },
+    };
+
+    fn descriptor(
+        usage: VertexAttributeUsage,
+        data_type: VertexAttributeDataType,
+        size: u8,
+        shader_location: u8,
+    ) -> VertexAttributeDescriptor {
+        VertexAttributeDescriptor {
+            usage,
+            data_type,
+            size,
+            divisor: 0,
+            shader_location,
+            normalized: false,
+            integer: false,
+        }
+    }
+
+    // Position (F32x3), Normal (F32x3), TexCoord0 (F32x2).
+    fn test_buffer() -> VertexBuffer {
+        let layout = [
+            descriptor(
+                VertexAttributeUsage::Position,
+                VertexAttributeDataType::F32,
+                3,
+                0,
+            ),
+            descriptor(
+                VertexAttributeUsage::Normal,
+                VertexAttributeDataType::F32,
+                3,
+                1,
+            ),
+            descriptor(
+                VertexAttributeUsage::TexCoord0,
+                VertexAttributeDataType::F32,
+                2,
+                2,
+            ),
+        ];
+        let data: Vec<f32> = vec![
+            1.0, 2.0, 3.0, 0.0, 1.0, 0.0, 0.25, 0.5, //
+            -1.0, -2.0, -3.0, 0.6, 0.0, -0.8, 0.75, 1.0, //
+            4.5, 0.0, -7.25, -0.267, 0.535, 0.802, 0.0, 0.125,
+        ];
+        VertexBuffer::new(3, &layout, data).unwrap()
+    }
+
+    fn usages(buffer: &VertexBuffer) -> Vec<VertexAttributeUsage> {
+        buffer.layout().iter().map(|a| a.usage).collect()
+    }
+
+    #[test]
+    fn test_f16_edge_values() {
+        assert_eq!(f32_to_f16(0.0), 0x0000);
+        assert_eq!(f32_to_f16(-0.0), 0x8000);
+        assert_eq!(f16_to_f32(0x0000).to_bits(), 0.0f32.to_bits());
+        assert_eq!(f16_to_f32(0x8000).to_bits(), (-0.0f32).to_bits());
+
+        assert_eq!(f32_to_f16(1.0), 0x3C00);
+        assert_eq!(f32_to_f16(-1.0), 0xBC00);
+        assert_eq!(f16_to_f32(0x3C00), 1.0);
+        assert_eq!(f16_to_f32(0xBC00), -1.0);
+
+        // Largest finite value and overflow.
+        assert_eq!(f16_to_f32(0x7BFF), 65504.0);
+        assert_eq!(f32_to_f16(65504.0), 0x7BFF);
+        assert_eq!(f32_to_f16(1.0e6), 0x7C00);
+        assert_eq!(f32_to_f16(-1.0e6), 0xFC00);
+
+        // Subnormals.
+        let smallest = 2.0f32.powi(-24);
+        assert_eq!(f16_to_f32(0x0001), smallest);
+        assert_eq!(f16_to_f32(0x8001), -smallest);
+        assert_eq!(f32_to_f16(smallest), 0x0001);
+        assert_eq!(f16_to_f32(0x03FF), 1023.0 * smallest);
+        assert_eq!(f32_to_f16(1023.0 * smallest), 0x03FF);
+        assert_eq!(f32_to_f16(1.0e-10), 0x0000);
+        assert_eq!(f32_to_f16(-1.0e-10), 0x8000);
+
+        assert_eq!(f32_to_f16(f32::INFINITY), 0x7C00);
+        assert_eq!(f32_to_f16(f32::NEG_INFINITY), 0xFC00);
+        assert_eq!(f16_to_f32(0x7C00), f32::INFINITY);
+        assert_eq!(f16_to_f32(0xFC00), f32::NEG_INFINITY);
+
+        let nan = f32_to_f16(f32::NAN);
+        assert_eq!(nan & 0x7C00, 0x7C00);
+        assert_ne!(nan & 0x03FF, 0);
+        assert!(f16_to_f32(0x7E00).is_nan());
+        assert!(f16_to_f32(0xFE00).is_nan());
+    }
+
+    #[test]
+    fn test_f16_round_trip() {
+        for half in 0..=u16::MAX {
+            let value = f16_to_f32(half);
+            if value.is_nan() {
+                assert!(f16_to_f32(f32_to_f16(value)).is_nan());
+            } else {
+                assert_eq!(f32_to_f16(value), half, "{:#06x}", half);
+            }
+        }
+    }
+
+    #[test]
+    fn test_packed_signed_sign_extension() {
+        let ty = VertexAttributeDataType::PackedI2_10_10_10Rev;
+
+        // W = 0b11 is -1, W = 0b10 is -2, W = 0b01 is 1.
+        assert_eq!(unpack_2_10_10_10(0b11 << 30, ty, false)[3], -1.0);
+        assert_eq!(unpack_2_10_10_10(0b10 << 30, ty, false)[3], -2.0);
+        assert_eq!(unpack_2_10_10_10(0b01 << 30, ty, false)[3], 1.0);
+
+        // X = 0x3FF is -1, X = 0x200 is -512.
+        assert_eq!(unpack_2_10_10_10(0x3FF, ty, false)[0], -1.0);
+        assert_eq!(unpack_2_10_10_10(0x200, ty, false)[0], -512.0);
+
+        // Unsigned variant must not sign-extend.
+        let ty = VertexAttributeDataType::PackedU2_10_10_10Rev;
+        assert_eq!(unpack_2_10_10_10(0b11 << 30, ty, false)[3], 3.0);
+        assert_eq!(unpack_2_10_10_10(0x3FF, ty, false)[0], 1023.0);
+    }
+
+    #[test]
+    fn test_packed_signed_normalized_clamping() {
+        let ty = VertexAttributeDataType::PackedI2_10_10_10Rev;
+
+        // The minimal values map to -1 instead of going below it.
+        let packed = 0x200 | (0x200 << 10) | (0x200 << 20) | (0b10 << 30);
+        assert_eq!(unpack_2_10_10_10(packed, ty, true), [-1.0; 4]);
+
+        let packed = 0x201 | (0b11 << 30);
+        let components = unpack_2_10_10_10(packed, ty, true);
+        assert_eq!(components[0], -1.0);
+        assert_eq!(components[3], -1.0);
+
+        // Packing clamps out of range values.
+        assert_eq!(
+            pack_2_10_10_10([-2.0, 2.0, -1.0, 1.0], ty, true),
+            pack_2_10_10_10([-1.0, 1.0, -1.0, 1.0], ty, true)
+        );
+    }
+
+    #[test]
+    fn test_packed_round_trip() {
+        let ty = VertexAttributeDataType::PackedI2_10_10_10Rev;
+        let components = [0.5, -0.25, 1.0, -1.0];
+        let unpacked = unpack_2_10_10_10(pack_2_10_10_10(components, ty, true), ty, true);
+        for (a, b) in components.iter().zip(unpacked) {
+            assert!((a - b).abs() <= 1.0 / 511.0, "{} != {}", a, b);
+        }
+        let components = [-512.0, 511.0, 7.0, -2.0];
+        let unpacked = unpack_2_10_10_10(pack_2_10_10_10(components, ty, false), ty, false);
+        assert_eq!(unpacked, components);
+
+        let ty = VertexAttributeDataType::PackedU2_10_10_10Rev;
+        let components = [0.0, 0.5, 1.0, 1.0 / 3.0];
+        let unpacked = unpack_2_10_10_10(pack_2_10_10_10(components, ty, true), ty, true);
+        for (a, b) in components.iter().zip(unpacked) {
+            assert!((a - b).abs() <= 1.0 / 1023.0, "{} != {}", a, b);
+        }
+        let components = [0.0, 1023.0, 512.0, 3.0];
+        let unpacked = unpack_2_10_10_10(pack_2_10_10_10(components, ty, false), ty, false);
+        assert_eq!(unpacked, components);
+    }
+
+    #[test]
+    fn test_deinterleave_interleave_round_trip() {
+        let buffer = test_buffer();
+
+        let streams = buffer
+            .deinterleave(&[
+                &[VertexAttributeUsage::Position],
+                &[
+                    VertexAttributeUsage::Normal,
+                    VertexAttributeUsage::TexCoord0,
+                ],
+            ])
+            .unwrap();
+        assert_eq!(streams.len(), 2);
+        assert_eq!(usages(&streams[0]), [VertexAttributeUsage::Position]);
+        assert_eq!(streams[0].vertex_size_in_byte(), 12);
+        assert_eq!(streams[0].vertex_count(), 3);
+        assert_eq!(streams[1].vertex_size_in_byte(), 20);
+        assert_eq!(streams[1].layout()[1].offset, 12);
+
+        let interleaved = VertexBuffer::interleave(&streams).unwrap();
+        assert_eq!(usages(&interleaved), usages(&buffer));
+        assert_eq!(
+            interleaved.vertex_size_in_byte(),
+            buffer.vertex_size_in_byte()
+        );
+        assert_eq!(interleaved.vertex_count(), buffer.vertex_count());
+        assert_eq!(interleaved.raw_data(), buffer.raw_data());
+    }
+
+    #[test]
+    fn test_deinterleave_missing_attribute() {
+        let buffer = test_buffer();
+        assert!(matches!(
+            buffer.deinterleave(&[&[VertexAttributeUsage::Color]]),
+            Err(ValidationError::MissingAttribute(
+                VertexAttributeUsage::Color
+            ))
+        ));
+    }
+
+    #[test]
+    fn test_interleave_vertex_count_mismatch() {
+        let buffer = test_buffer();
+        let mut streams = buffer
+            .deinterleave(&[
+                &[VertexAttributeUsage::Position],
+                &[VertexAttributeUsage::Normal],
+            ])
+            .unwrap();
+        streams[1].modify().remove_last_vertex();
+        assert!(matches!(
+            VertexBuffer::interleave(&streams),
+            Err(ValidationError::VertexCountMismatch {
+                expected: 3,
+                actual: 2
+            })
+        ));
+    }
+
+    #[test]
+    fn test_with_layout_converts_data() {
+        let buffer = test_buffer();
+        let converted = buffer
+            .with_layout(&[
+                descriptor(
+                    VertexAttributeUsage::TexCoord0,
+                    VertexAttributeDataType::F16,
+                    2,
+                    0,
+                ),
+                descriptor(
+                    VertexAttributeUsage::Position,
+                    VertexAttributeDataType::F32,
+                    3,
+                    1,
+                ),
+            ])
+            .unwrap();
+        assert_eq!(converted.vertex_size_in_byte(), 16);
+        for i in 0..3 {
+            let src = buffer.get(i).unwrap();
+            let dst = converted.get(i).unwrap();
+            // All texture coordinates of the test buffer are exact in f16.
+            assert_eq!(
+                dst.read_2_f32(VertexAttributeUsage::TexCoord0).unwrap(),
+                src.read_2_f32(VertexAttributeUsage::TexCoord0).unwrap()
+            );
+            assert_eq!(
+                dst.read_3_f32(VertexAttributeUsage::Position).unwrap(),
+                src.read_3_f32(VertexAttributeUsage::Position).unwrap()
+            );
+        }
+    }
+
+    #[test]
+    fn test_remove_attribute() {
+        let original = test_buffer();
+        let mut buffer = original.clone();
+
+        buffer
+            .modify()
+            .remove_attribute(VertexAttributeUsage::Normal)
+            .unwrap();
+        assert_eq!(
+            usages(&buffer),
+            [
+                VertexAttributeUsage::Position,
+                VertexAttributeUsage::TexCoord0
+            ]
+        );
+        assert_eq!(buffer.vertex_size_in_byte(), 20);
+        assert_eq!(buffer.layout()[1].offset, 12);
+        for i in 0..3 {
+            let src = original.get(i).unwrap();
+            let dst = buffer.get(i).unwrap();
+            assert_eq!(
+                dst.read_3_f32(VertexAttributeUsage::Position).unwrap(),
+                src.read_3_f32(VertexAttributeUsage::Position).unwrap()
+            );
+            assert_eq!(
+                dst.read_2_f32(VertexAttributeUsage::TexCoord0).unwrap(),
+                src.read_2_f32(VertexAttributeUsage::TexCoord0).unwrap()
+            );
+        }
+
+        assert!(matches!(
+            buffer
+                .modify()
+                .remove_attribute(VertexAttributeUsage::Normal),
+            Err(ValidationError::MissingAttribute(
+                VertexAttributeUsage::Normal
+            ))
+        ));
+    }
+
+    #[test]
+    fn test_convert_attribute_packed_round_trip() {
+        let original = test_buffer();
+        let mut buffer = original.clone();
+
+        buffer
+            .modify()
+            .convert_attribute(
+                VertexAttributeUsage::Normal,
+                VertexAttributeDataType::PackedI2_10_10_10Rev,
+            )
+            .unwrap();
+        let normal = buffer.layout()[1];
+        assert_eq!(
+            normal.data_type,
+            VertexAttributeDataType::PackedI2_10_10_10Rev
+        );
+        assert_eq!(normal.size, 4);
+        assert!(normal.normalized);
+        assert_eq!(buffer.vertex_size_in_byte(), 24);
+
+        buffer
+            .modify()
+            .convert_attribute(VertexAttributeUsage::Normal, VertexAttributeDataType::F32)
+            .unwrap();
+        let normal = buffer.layout()[1];
+        assert_eq!(normal.data_type, VertexAttributeDataType::F32);
+        assert!(!normal.normalized);
+
+        for i in 0..3 {
+            let src = original.get(i).unwrap();
+            let dst = buffer.get(i).unwrap();
+
+            let expected = src.read_3_f32(VertexAttributeUsage::Normal).unwrap();
+            let actual = dst.read_4_f32(VertexAttributeUsage::Normal).unwrap();
+            for (a, b) in expected.iter().zip(actual.iter()) {
+                assert!((a - b).abs() <= 1.0 / 511.0, "{} != {}", a, b);
+            }
+            // Missing component is filled with zero.
+            assert_eq!(actual.w, 0.0);
+
+            assert_eq!(
+                dst.read_3_f32(VertexAttributeUsage::Position).unwrap(),
+                src.read_3_f32(VertexAttributeUsage::Position).unwrap()
+            );
+        }
+    }
+
+    #[test]
+    fn test_reorder_layout() {
+        let original = test_buffer();
+        let mut buffer = original.clone();
+
+        let order = [
+            VertexAttributeUsage::TexCoord0,
+            VertexAttributeUsage::Position,
+            VertexAttributeUsage::Normal,
+        ];
+        buffer.modify().reorder_layout(&order).unwrap();
+        assert_eq!(usages(&buffer), order);
+        assert_eq!(buffer.layout()[0].offset, 0);
+        assert_eq!(buffer.layout()[1].offset, 8);
+        assert_eq!(buffer.layout()[2].offset, 20);
+        for i in 0..3 {
+            let src = original.get(i).unwrap();
+            let dst = buffer.get(i).unwrap();
+            for usage in [VertexAttributeUsage::Position, VertexAttributeUsage::Normal] {
+                assert_eq!(
+                    dst.read::<Vector3<f32>>(usage).unwrap(),
+                    src.read::<Vector3<f32>>(usage).unwrap()
+                );
+            }
+            assert_eq!(
+                dst.read::<Vector2<f32>>(VertexAttributeUsage::TexCoord0)
+                    .unwrap(),
+                src.read::<Vector2<f32>>(VertexAttributeUsage::TexCoord0)
+                    .unwrap()
+            );
+        }
+    }
+
+    #[test]
+    fn test_reorder_layout_missing_usage() {
+        let mut buffer = test_buffer();
+
+        assert!(matches!(
+            buffer
+                .modify()
+                .reorder_layout(&[VertexAttributeUsage::Position, VertexAttributeUsage::Normal]),
+            Err(ValidationError::MissingAttribute(
+                VertexAttributeUsage::TexCoord0
+            ))
+        ));
+
+        assert!(matches!(
+            buffer.modify().reorder_layout(&[
+                VertexAttributeUsage::Position,
+                VertexAttributeUsage::Normal,
+                VertexAttributeUsage::TexCoord0,
+                VertexAttributeUsage::Color,
+            ]),
+            Err(ValidationError::MissingAttribute(
+                VertexAttributeUsage::Color
+            ))
+        ));
+
+        // Failed reordering leaves the buffer intact.
+        assert_eq!(usages(&buffer), usages(&test_buffer()));
+        assert_eq!(buffer.raw_data(), test_buffer().raw_data());
+    }
+
+    #[test]
+    fn test_add_attribute() {
+        let mut buffer = test_buffer();
+
+        buffer
+            .modify()
+            .add_attribute(
+                descriptor(
+                    VertexAttributeUsage::Color,
+                    VertexAttributeDataType::U8,
+                    4,
+                    3,
+                ),
+                [255u8, 0, 128, 255],
+            )
+            .unwrap();
+        assert_eq!(buffer.vertex_size_in_byte(), 36);
+        assert_eq!(buffer.layout()[3].offset, 32);
+        for i in 0..3 {
+            assert_eq!(
+                buffer
+                    .get(i)
+                    .unwrap()
+                    .read_4_u8(VertexAttributeUsage::Color)
+                    .unwrap(),
+                Vector4::new(255, 0, 128, 255)
+            );
+        }
+
+        // Fill value does not match the attribute.
+        assert!(matches!(
+            buffer.modify().add_attribute(
+                descriptor(
+                    VertexAttributeUsage::TexCoord1,
+                    VertexAttributeDataType::F32,
+                    2,
+                    4
+                ),
+                [0.0f32; 3],
+            ),
+            Err(ValidationError::InvalidDataSize {
+                expected: 8,
+                actual: 12
+            })
+        ));
+        assert!(matches!(
+            buffer.modify().add_attribute(
+                descriptor(
+                    VertexAttributeUsage::TexCoord1,
+                    VertexAttributeDataType::F32,
+                    0,
+                    4
+                ),
+                (),
+            ),
+            Err(ValidationError::InvalidAttributeSize(0))
+        ));
+        assert!(matches!(
+            buffer.modify().add_attribute(
+                descriptor(
+                    VertexAttributeUsage::Color,
+                    VertexAttributeDataType::U8,
+                    4,
+                    4
+                ),
+                [0u8; 4],
+            ),
+            Err(ValidationError::DuplicatedAttributeDescriptor)
+        ));
+        assert_eq!(buffer.vertex_size_in_byte(), 36);
+    }
+}